@@ -1,3 +1,7 @@
 glslc.exe "shaders/default.vert" -o "shaders/default.vert.spv"
 glslc.exe "shaders/default.frag" -o "shaders/default.frag.spv"
+glslc.exe "shaders/rect.vert" -o "shaders/rect.vert.spv"
+glslc.exe "shaders/rect.frag" -o "shaders/rect.frag.spv"
+glslc.exe "shaders/sprite.vert" -o "shaders/sprite.vert.spv"
+glslc.exe "shaders/sprite.frag" -o "shaders/sprite.frag.spv"
 pause
\ No newline at end of file
@@ -0,0 +1,7 @@
+#!/bin/sh
+glslc "shaders/default.vert" -o "shaders/default.vert.spv"
+glslc "shaders/default.frag" -o "shaders/default.frag.spv"
+glslc "shaders/rect.vert" -o "shaders/rect.vert.spv"
+glslc "shaders/rect.frag" -o "shaders/rect.frag.spv"
+glslc "shaders/sprite.vert" -o "shaders/sprite.vert.spv"
+glslc "shaders/sprite.frag" -o "shaders/sprite.frag.spv"
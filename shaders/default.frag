@@ -3,9 +3,10 @@
 layout(location = 0) out vec4 out_color;
 
 layout(binding = 0) uniform UBO {
-    vec3 color;
+    vec4 color;
 };
 
 void main() {
-    out_color = vec4(color, 1.0);
+    // premultiplied so it blends with PipelineSettings::PREMULTIPLIED
+    out_color = vec4(color.rgb * color.a, color.a);
 }
@@ -2,6 +2,11 @@
 
 layout(location = 0) in vec2 v_pos;
 
+// the start of PushConstants in descriptor.rs
+layout(push_constant) uniform Push {
+    mat4 projection;
+};
+
 void main() {
-    gl_Position = vec4(v_pos, 0.0, 1.0);
+    gl_Position = projection * vec4(v_pos, 0.0, 1.0);
 }
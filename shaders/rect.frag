@@ -0,0 +1,16 @@
+#version 460
+
+layout(location = 0) out vec4 out_color;
+
+// matches PushConstants in descriptor.rs
+layout(push_constant) uniform Push {
+    mat4 projection;
+    vec4 rect;
+    vec4 uv_rect;
+    vec4 color;
+};
+
+void main() {
+    // premultiplied so it blends with PipelineSettings::PREMULTIPLIED
+    out_color = vec4(color.rgb * color.a, color.a);
+}
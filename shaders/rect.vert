@@ -0,0 +1,17 @@
+#version 460
+
+layout(location = 0) in vec2 v_pos;
+
+// matches PushConstants in descriptor.rs
+layout(push_constant) uniform Push {
+    mat4 projection;
+    vec4 rect;
+    vec4 uv_rect;
+    vec4 color;
+};
+
+void main() {
+    vec2 px = rect.xy + v_pos * rect.zw;
+    // the camera's or the window's, depending on the layer
+    gl_Position = projection * vec4(px, 0.0, 1.0);
+}
@@ -0,0 +1,22 @@
+#version 460
+
+layout(location = 0) in vec2 f_uv;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 1, binding = 0) uniform texture2D tex;
+layout(set = 1, binding = 1) uniform sampler tex_sampler;
+
+// matches PushConstants in descriptor.rs
+layout(push_constant) uniform Push {
+    mat4 projection;
+    vec4 rect;
+    vec4 uv_rect;
+    vec4 color;
+};
+
+void main() {
+    // textures are straight alpha, tinted then premultiplied
+    vec4 texel = texture(sampler2D(tex, tex_sampler), f_uv) * color;
+    out_color = vec4(texel.rgb * texel.a, texel.a);
+}
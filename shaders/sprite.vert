@@ -0,0 +1,20 @@
+#version 460
+
+layout(location = 0) in vec2 v_pos;
+
+layout(location = 0) out vec2 f_uv;
+
+// matches PushConstants in descriptor.rs
+layout(push_constant) uniform Push {
+    mat4 projection;
+    vec4 rect;
+    vec4 uv_rect;
+    vec4 color;
+};
+
+void main() {
+    vec2 px = rect.xy + v_pos * rect.zw;
+    // the camera's or the window's, depending on the layer
+    gl_Position = projection * vec4(px, 0.0, 1.0);
+    f_uv = uv_rect.xy + v_pos * uv_rect.zw;
+}
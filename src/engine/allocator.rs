@@ -0,0 +1,470 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+use ash::vk;
+use super::find_memory_type;
+
+/// the allocator is shared between the engine and every buffer/image so they can give their memory back on drop
+pub type SharedAllocator = Rc<RefCell<Allocator>>;
+
+/// carves buffers and images out of big `vk::DeviceMemory` blocks instead of calling `allocate_memory` for each one
+pub struct Allocator {
+    blocks: Vec<Option<Block>>,
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    // vulkan handles
+    device: ash::Device
+}
+
+/// buffers and optimal images never share a block so `buffer_image_granularity` can't bite us
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationKind {
+    Linear,
+    Optimal
+}
+
+#[derive(Debug)]
+pub struct Allocation {
+    block: usize,
+    memory: vk::DeviceMemory,
+    offset: u64,
+    size: u64,
+    mapped: *mut u8
+}
+
+struct Block {
+    memory: vk::DeviceMemory,
+    memory_type: u32,
+    kind: AllocationKind,
+    size: u64,
+    /// whole block stays mapped for its lifetime if its host visible, you can't map the same memory twice
+    mapped: *mut u8,
+    /// (offset, size), sorted by offset and never touching each other
+    free_ranges: Vec<(u64, u64)>,
+    allocation_count: u32
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AllocatorStats {
+    pub block_count: u32,
+    pub allocation_count: u32,
+    /// bytes allocated from the driver
+    pub reserved_bytes: u64,
+    /// bytes handed out to buffers/images
+    pub used_bytes: u64,
+    pub memory_types: Vec<MemoryTypeStats>
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTypeStats {
+    pub memory_type: u32,
+    pub block_count: u32,
+    pub allocation_count: u32,
+    pub reserved_bytes: u64,
+    pub used_bytes: u64
+}
+
+impl Allocator {
+    /// 16MiB, anything bigger gets a block to itself
+    pub const BLOCK_SIZE: u64 = 16 * 1024 * 1024;
+
+    pub fn new(device: ash::Device, memory_properties: vk::PhysicalDeviceMemoryProperties) -> Allocator {
+        Allocator {
+            blocks: Vec::new(),
+            memory_properties,
+            device
+        }
+    }
+
+    pub fn allocate(
+        &mut self,
+        requirements: vk::MemoryRequirements,
+        memory_flags: vk::MemoryPropertyFlags,
+        kind: AllocationKind
+    ) -> Allocation {
+        let memory_type = find_memory_type(
+            self.memory_properties,
+            requirements.memory_type_bits,
+            memory_flags
+        ).expect("No suitable memory type found");
+
+        let alignment = requirements.alignment.max(1);
+
+        for (index, block) in self.blocks.iter_mut().enumerate() {
+            let Some(block) = block else { continue };
+            if block.memory_type != memory_type || block.kind != kind {
+                continue;
+            }
+
+            if let Some(offset) = block.take_range(requirements.size, alignment) {
+                return Allocation {
+                    block: index,
+                    memory: block.memory,
+                    offset,
+                    size: requirements.size,
+                    mapped: block.mapped_at(offset)
+                };
+            }
+        }
+
+        let block = self.create_block(memory_type, kind, requirements.size.max(Allocator::BLOCK_SIZE));
+        let index = match self.blocks.iter().position(|b| b.is_none()) {
+            Some(index) => {
+                self.blocks[index] = Some(block);
+                index
+            }
+            None => {
+                self.blocks.push(Some(block));
+                self.blocks.len() - 1
+            }
+        };
+
+        let block = self.blocks[index].as_mut().unwrap();
+        let offset = block.take_range(requirements.size, alignment).unwrap();
+
+        Allocation {
+            block: index,
+            memory: block.memory,
+            offset,
+            size: requirements.size,
+            mapped: block.mapped_at(offset)
+        }
+    }
+
+    /// allocates memory for `buffer` and binds it
+    pub fn allocate_buffer(&mut self, buffer: vk::Buffer, memory_flags: vk::MemoryPropertyFlags) -> Allocation {
+        unsafe {
+            let requirements = self.device.get_buffer_memory_requirements(buffer);
+            let allocation = self.allocate(requirements, memory_flags, AllocationKind::Linear);
+            self.device.bind_buffer_memory(buffer, allocation.memory, allocation.offset).unwrap();
+
+            allocation
+        }
+    }
+
+    /// allocates memory for an optimally tiled `image` and binds it
+    pub fn allocate_image(&mut self, image: vk::Image, memory_flags: vk::MemoryPropertyFlags) -> Allocation {
+        unsafe {
+            let requirements = self.device.get_image_memory_requirements(image);
+            let allocation = self.allocate(requirements, memory_flags, AllocationKind::Optimal);
+            self.device.bind_image_memory(image, allocation.memory, allocation.offset).unwrap();
+
+            allocation
+        }
+    }
+
+    /// gives the range back to its block, empty blocks are kept around until `release_empty_blocks`
+    pub fn free(&mut self, allocation: &Allocation) {
+        let block = self.blocks[allocation.block].as_mut().expect("Allocation freed twice");
+
+        block.give_back_range(allocation.offset, allocation.size);
+        block.allocation_count -= 1;
+    }
+
+    /// hands blocks with nothing left in them back to the driver, live allocations are never moved.
+    /// returns how many bytes were released
+    pub fn release_empty_blocks(&mut self) -> u64 {
+        let mut released = 0;
+
+        for block in self.blocks.iter_mut() {
+            if block.as_ref().is_some_and(|b| b.allocation_count == 0) {
+                let block = block.take().unwrap();
+                released += block.size;
+
+                unsafe {
+                    if !block.mapped.is_null() {
+                        self.device.unmap_memory(block.memory);
+                    }
+                    self.device.free_memory(block.memory, None);
+                }
+            }
+        }
+
+        while let Some(None) = self.blocks.last() {
+            self.blocks.pop();
+        }
+
+        released
+    }
+
+    pub fn stats(&self) -> AllocatorStats {
+        let mut stats = AllocatorStats::default();
+
+        for block in self.blocks.iter().flatten() {
+            let free_bytes = block.free_ranges.iter().map(|r| r.1).sum::<u64>();
+
+            let type_stats = match stats.memory_types.iter_mut().find(|s| s.memory_type == block.memory_type) {
+                Some(type_stats) => type_stats,
+                None => {
+                    stats.memory_types.push(MemoryTypeStats {
+                        memory_type: block.memory_type,
+                        ..Default::default()
+                    });
+                    stats.memory_types.last_mut().unwrap()
+                }
+            };
+
+            type_stats.block_count += 1;
+            type_stats.allocation_count += block.allocation_count;
+            type_stats.reserved_bytes += block.size;
+            type_stats.used_bytes += block.size - free_bytes;
+
+            stats.block_count += 1;
+            stats.allocation_count += block.allocation_count;
+            stats.reserved_bytes += block.size;
+            stats.used_bytes += block.size - free_bytes;
+        }
+
+        stats.memory_types.sort_by_key(|s| s.memory_type);
+        stats
+    }
+
+    fn create_block(&self, memory_type: u32, kind: AllocationKind, size: u64) -> Block {
+        unsafe {
+            let memory = self.device.allocate_memory(
+                &vk::MemoryAllocateInfo::builder()
+                    .allocation_size(size)
+                    .memory_type_index(memory_type),
+                None
+            ).unwrap();
+
+            let host_visible = self.memory_properties.memory_types[memory_type as usize]
+                .property_flags
+                .contains(vk::MemoryPropertyFlags::HOST_VISIBLE);
+
+            let mapped = if host_visible {
+                self.device.map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()).unwrap() as *mut u8
+            } else {
+                std::ptr::null_mut()
+            };
+
+            Block {
+                memory,
+                memory_type,
+                kind,
+                size,
+                mapped,
+                free_ranges: vec![(0, size)],
+                allocation_count: 0
+            }
+        }
+    }
+}
+
+impl Drop for Allocator {
+    fn drop(&mut self) {
+        unsafe {
+            for block in self.blocks.iter().flatten() {
+                if !block.mapped.is_null() {
+                    self.device.unmap_memory(block.memory);
+                }
+                self.device.free_memory(block.memory, None);
+            }
+        }
+    }
+}
+
+impl Block {
+    /// first fit, returns the aligned offset
+    fn take_range(&mut self, size: u64, alignment: u64) -> Option<u64> {
+        let (index, offset) = self.free_ranges.iter().enumerate().find_map(|(i, &(start, len))| {
+            let aligned = align_up(start, alignment);
+            (aligned + size <= start + len).then_some((i, aligned))
+        })?;
+
+        let (start, len) = self.free_ranges.remove(index);
+        let end = start + len;
+
+        // put back whatever is left on either side of the allocation
+        if offset + size < end {
+            self.free_ranges.insert(index, (offset + size, end - (offset + size)));
+        }
+        if start < offset {
+            self.free_ranges.insert(index, (start, offset - start));
+        }
+
+        self.allocation_count += 1;
+        Some(offset)
+    }
+
+    /// panics if any of the range is already free, merging it again would corrupt the free list
+    fn give_back_range(&mut self, offset: u64, size: u64) {
+        assert!(offset + size <= self.size, "Freed range {offset}+{size} is outside the block");
+
+        let index = self.free_ranges.partition_point(|r| r.0 < offset);
+        let overlaps_prev = index > 0 && {
+            let prev = self.free_ranges[index - 1];
+            prev.0 + prev.1 > offset
+        };
+        let overlaps_next = self.free_ranges.get(index).is_some_and(|next| offset + size > next.0);
+        assert!(!overlaps_prev && !overlaps_next, "Range {offset}+{size} freed twice");
+
+        self.free_ranges.insert(index, (offset, size));
+
+        // merge with the next range first so the index stays valid
+        if index + 1 < self.free_ranges.len() {
+            let (start, len) = self.free_ranges[index];
+            let next = self.free_ranges[index + 1];
+            if start + len == next.0 {
+                self.free_ranges[index].1 += next.1;
+                self.free_ranges.remove(index + 1);
+            }
+        }
+        if index > 0 {
+            let prev = self.free_ranges[index - 1];
+            let (start, len) = self.free_ranges[index];
+            if prev.0 + prev.1 == start {
+                self.free_ranges[index - 1].1 += len;
+                self.free_ranges.remove(index);
+            }
+        }
+    }
+
+    fn mapped_at(&self, offset: u64) -> *mut u8 {
+        if self.mapped.is_null() {
+            std::ptr::null_mut()
+        } else {
+            unsafe { self.mapped.add(offset as usize) }
+        }
+    }
+}
+
+impl Allocation {
+    pub fn memory(&self) -> vk::DeviceMemory {
+        self.memory
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// `None` if the memory isn't host visible
+    pub fn mapped_ptr(&self) -> Option<*mut u8> {
+        (!self.mapped.is_null()).then_some(self.mapped)
+    }
+}
+
+fn align_up(value: u64, alignment: u64) -> u64 {
+    value.div_ceil(alignment) * alignment
+}
+
+impl fmt::Display for AllocatorStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} allocations in {} blocks, {} / {} KiB used",
+            self.allocation_count,
+            self.block_count,
+            self.used_bytes / 1024,
+            self.reserved_bytes / 1024
+        )?;
+
+        for type_stats in &self.memory_types {
+            writeln!(
+                f,
+                "  memory type {}: {} allocations in {} blocks, {} / {} KiB used",
+                type_stats.memory_type,
+                type_stats.allocation_count,
+                type_stats.block_count,
+                type_stats.used_bytes / 1024,
+                type_stats.reserved_bytes / 1024
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(size: u64) -> Block {
+        Block {
+            memory: vk::DeviceMemory::null(),
+            memory_type: 0,
+            kind: AllocationKind::Linear,
+            size,
+            mapped: std::ptr::null_mut(),
+            free_ranges: vec![(0, size)],
+            allocation_count: 0
+        }
+    }
+
+    #[test]
+    fn align_up_rounds_to_the_next_multiple() {
+        assert_eq!(align_up(0, 256), 0);
+        assert_eq!(align_up(1, 256), 256);
+        assert_eq!(align_up(256, 256), 256);
+        assert_eq!(align_up(257, 1), 257);
+    }
+
+    #[test]
+    fn take_range_is_first_fit_and_aligned() {
+        let mut block = block(1024);
+
+        assert_eq!(block.take_range(100, 1), Some(0));
+        assert_eq!(block.take_range(100, 64), Some(128));
+        // the gap the alignment left is still free
+        assert_eq!(block.free_ranges, vec![(100, 28), (228, 796)]);
+        assert_eq!(block.take_range(20, 4), Some(100));
+        assert_eq!(block.allocation_count, 3);
+    }
+
+    #[test]
+    fn take_range_fails_when_nothing_fits() {
+        let mut block = block(256);
+
+        assert_eq!(block.take_range(200, 1), Some(0));
+        assert_eq!(block.take_range(100, 1), None);
+        assert_eq!(block.allocation_count, 1);
+    }
+
+    #[test]
+    fn give_back_range_merges_neighbours() {
+        let mut block = block(300);
+        let a = block.take_range(100, 1).unwrap();
+        let b = block.take_range(100, 1).unwrap();
+        let c = block.take_range(100, 1).unwrap();
+        assert!(block.free_ranges.is_empty());
+
+        block.give_back_range(a, 100);
+        block.give_back_range(c, 100);
+        assert_eq!(block.free_ranges, vec![(0, 100), (200, 100)]);
+
+        // joins both sides back into one range
+        block.give_back_range(b, 100);
+        assert_eq!(block.free_ranges, vec![(0, 300)]);
+    }
+
+    #[test]
+    fn freed_ranges_get_reused() {
+        let mut block = block(256);
+        let a = block.take_range(128, 1).unwrap();
+        block.take_range(128, 1).unwrap();
+
+        block.give_back_range(a, 128);
+        assert_eq!(block.take_range(64, 64), Some(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "freed twice")]
+    fn double_free_panics() {
+        let mut block = block(256);
+        let a = block.take_range(64, 1).unwrap();
+        block.take_range(64, 1).unwrap();
+
+        block.give_back_range(a, 64);
+        block.give_back_range(a, 64);
+    }
+
+    #[test]
+    #[should_panic(expected = "freed twice")]
+    fn freeing_into_a_free_range_panics() {
+        let mut block = block(256);
+        block.take_range(64, 1).unwrap();
+
+        block.give_back_range(128, 16);
+    }
+}
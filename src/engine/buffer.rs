@@ -1,25 +1,37 @@
-use std::mem::size_of;
+use std::{fmt, marker::PhantomData, mem::size_of};
 use ash::vk;
-use super::find_memory_type;
+use super::allocator::{Allocation, SharedAllocator};
 
-#[derive(Clone)]
-pub struct Buffer {
+/// gpu buffer holding `count` elements of `T`.
+/// not `Clone` on purpose, the buffer owns its vulkan handle and memory
+pub struct Buffer<T: Copy> {
     buffer: vk::Buffer,
-    memory: vk::DeviceMemory,
-    size: u64,
+    allocation: Allocation,
     count: u32,
     // vulkan handles
-    device: ash::Device
+    device: ash::Device,
+    allocator: SharedAllocator,
+    _marker: PhantomData<T>
 }
 
-impl Buffer {
-    pub fn new<T>(
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferError {
+    /// tried to write `len` elements at `offset` into a buffer of `count` elements
+    Overflow {
+        offset: usize,
+        len: usize,
+        count: usize
+    }
+}
+
+impl<T: Copy> Buffer<T> {
+    pub fn new(
         data: &[T],
         usage: vk::BufferUsageFlags,
         device: ash::Device,
-        device_memory_properties: vk::PhysicalDeviceMemoryProperties,
+        allocator: SharedAllocator,
         memory_flags: vk::MemoryPropertyFlags
-    ) -> Buffer {
+    ) -> Buffer<T> {
         unsafe {
             let buffer = device.create_buffer(
                 &vk::BufferCreateInfo::builder()
@@ -30,33 +42,19 @@ impl Buffer {
                 None
             ).unwrap();
 
-            let memory_requirements = device.get_buffer_memory_requirements(buffer);
+            let allocation = allocator.borrow_mut().allocate_buffer(buffer, memory_flags);
 
-            let buffer_memory = device.allocate_memory(
-                &vk::MemoryAllocateInfo::builder()
-                    .allocation_size(memory_requirements.size)
-                    .memory_type_index(
-                        find_memory_type(
-                            device_memory_properties,
-                            memory_requirements.memory_type_bits,
-                            memory_flags
-                        ).unwrap()
-                    ),
-                None
-            ).unwrap();
-
-            device.bind_buffer_memory(buffer, buffer_memory, 0).unwrap();
-            let buffer_ptr = device.map_memory(buffer_memory, 0, data.len() as u64 * size_of::<T>() as u64, vk::MemoryMapFlags::empty()).unwrap() as *mut T;
-            buffer_ptr.copy_from_nonoverlapping(data.as_ptr(), data.len());
-            device.unmap_memory(buffer_memory);
-
-            Buffer {
+            let buffer = Buffer {
                 buffer,
-                memory: buffer_memory,
-                size: data.len() as u64 * size_of::<T>() as u64,
+                allocation,
                 count: data.len() as u32,
-                device
-            }
+                device,
+                allocator,
+                _marker: PhantomData
+            };
+            buffer.set_buffer(data).unwrap();
+
+            buffer
         }
     }
 
@@ -68,20 +66,49 @@ impl Buffer {
         self.count
     }
 
-    pub fn set_buffer<T>(&self, data: &[T]) {
+    /// overwrites the start of the buffer with `data`
+    pub fn set_buffer(&self, data: &[T]) -> Result<(), BufferError> {
+        self.set_buffer_at(0, data)
+    }
+
+    /// overwrites `data.len()` elements starting at element `offset`
+    pub fn set_buffer_at(&self, offset: usize, data: &[T]) -> Result<(), BufferError> {
+        // checked so a huge offset can't wrap around past the bounds check
+        if offset.checked_add(data.len()).is_none_or(|end| end > self.count as usize) {
+            return Err(BufferError::Overflow {
+                offset,
+                len: data.len(),
+                count: self.count as usize
+            });
+        }
+
         unsafe {
-            let data_ptr = self.device.map_memory(self.memory, 0, self.size, vk::MemoryMapFlags::empty()).unwrap() as *mut T;
-            data_ptr.copy_from_nonoverlapping(data.as_ptr(), data.len());
-            self.device.unmap_memory(self.memory);
+            let data_ptr = self.allocation.mapped_ptr().expect("Buffer memory is not host visible") as *mut T;
+            data_ptr.add(offset).copy_from_nonoverlapping(data.as_ptr(), data.len());
         }
+
+        Ok(())
     }
 }
 
-impl Drop for Buffer {
+impl<T: Copy> Drop for Buffer<T> {
     fn drop(&mut self) {
         unsafe {
             self.device.destroy_buffer(self.buffer, None);
-            self.device.free_memory(self.memory, None);
         }
+        self.allocator.borrow_mut().free(&self.allocation);
     }
 }
+
+impl fmt::Display for BufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BufferError::Overflow { offset, len, count } => write!(
+                f,
+                "tried to write {len} elements at offset {offset} into a buffer of {count} elements"
+            )
+        }
+    }
+}
+
+impl std::error::Error for BufferError {}
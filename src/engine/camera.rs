@@ -0,0 +1,154 @@
+use super::projection::{ortho, Mat4};
+
+/// an orthographic 2d camera over the engine's pixel space, everything but the hud layer is drawn through it.
+/// zoom is screen pixels per world pixel so 2 makes things twice as big
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// world point in the middle of the window
+    center: [f32; 2],
+    zoom: f32
+}
+
+impl Camera {
+    pub const MIN_ZOOM: f32 = 0.05;
+    pub const MAX_ZOOM: f32 = 20.0;
+
+    /// zoom 1 looking at `center`
+    pub fn new(center: [f32; 2]) -> Camera {
+        Camera {
+            center,
+            zoom: 1.0
+        }
+    }
+
+    pub fn center(&self) -> [f32; 2] {
+        self.center
+    }
+
+    pub fn set_center(&mut self, center: [f32; 2]) {
+        self.center = center;
+    }
+
+    /// moves by `delta` world pixels
+    pub fn pan(&mut self, delta: [f32; 2]) {
+        self.center = [self.center[0] + delta[0], self.center[1] + delta[1]];
+    }
+
+    /// moves by `delta` screen pixels, so a drag moves the same amount at any zoom
+    pub fn pan_screen(&mut self, delta: [f32; 2]) {
+        self.pan([delta[0] / self.zoom, delta[1] / self.zoom]);
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// clamped to `MIN_ZOOM..=MAX_ZOOM`
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom.clamp(Camera::MIN_ZOOM, Camera::MAX_ZOOM);
+    }
+
+    /// zooms by `factor` keeping whatever is under `screen_point` where it is, like zooming to the cursor
+    pub fn zoom_at(&mut self, factor: f32, screen_point: [f32; 2], viewport: [f32; 2]) {
+        let before = self.screen_to_world(screen_point, viewport);
+        self.set_zoom(self.zoom * factor);
+        let after = self.screen_to_world(screen_point, viewport);
+        self.pan([before[0] - after[0], before[1] - after[1]]);
+    }
+
+    /// centres on a world rect and zooms so all of it fits in the viewport
+    pub fn fit(&mut self, position: [f32; 2], size: [f32; 2], viewport: [f32; 2]) {
+        self.center = [position[0] + size[0] / 2.0, position[1] + size[1] / 2.0];
+        self.set_zoom((viewport[0] / size[0]).min(viewport[1] / size[1]));
+    }
+
+    pub fn screen_to_world(&self, point: [f32; 2], viewport: [f32; 2]) -> [f32; 2] {
+        [
+            self.center[0] + (point[0] - viewport[0] / 2.0) / self.zoom,
+            self.center[1] + (point[1] - viewport[1] / 2.0) / self.zoom
+        ]
+    }
+
+    pub fn world_to_screen(&self, point: [f32; 2], viewport: [f32; 2]) -> [f32; 2] {
+        [
+            (point[0] - self.center[0]) * self.zoom + viewport[0] / 2.0,
+            (point[1] - self.center[1]) * self.zoom + viewport[1] / 2.0
+        ]
+    }
+
+    /// world pixels to clip space for a `viewport` sized window, same axes as `projection::ortho`
+    pub fn view_projection(&self, viewport: [f32; 2]) -> Mat4 {
+        let half = [viewport[0] / 2.0 / self.zoom, viewport[1] / 2.0 / self.zoom];
+        ortho(
+            self.center[0] - half[0],
+            self.center[0] + half[0],
+            self.center[1] - half[1],
+            self.center[1] + half[1]
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIEWPORT: [f32; 2] = [800.0, 600.0];
+
+    fn assert_near(a: [f32; 2], b: [f32; 2]) {
+        assert!((a[0] - b[0]).abs() < 1e-3 && (a[1] - b[1]).abs() < 1e-3, "{a:?} != {b:?}");
+    }
+
+    fn camera() -> Camera {
+        let mut camera = Camera::new([120.0, -40.0]);
+        camera.set_zoom(2.5);
+        camera
+    }
+
+    #[test]
+    fn screen_world_round_trip() {
+        let camera = camera();
+
+        for point in [[0.0, 0.0], [400.0, 300.0], [799.0, 12.5], [-50.0, 900.0]] {
+            assert_near(camera.world_to_screen(camera.screen_to_world(point, VIEWPORT), VIEWPORT), point);
+            assert_near(camera.screen_to_world(camera.world_to_screen(point, VIEWPORT), VIEWPORT), point);
+        }
+        // the centre of the window is what the camera looks at
+        assert_near(camera.screen_to_world([400.0, 300.0], VIEWPORT), camera.center());
+    }
+
+    #[test]
+    fn zoom_at_keeps_the_anchor_still() {
+        let mut camera = camera();
+        let anchor = [650.0, 120.0];
+        let world = camera.screen_to_world(anchor, VIEWPORT);
+
+        for factor in [2.0, 0.5, 1.3] {
+            camera.zoom_at(factor, anchor, VIEWPORT);
+            assert_near(camera.world_to_screen(world, VIEWPORT), anchor);
+        }
+    }
+
+    #[test]
+    fn set_zoom_clamps() {
+        let mut camera = camera();
+
+        camera.set_zoom(1000.0);
+        assert_eq!(camera.zoom(), Camera::MAX_ZOOM);
+        camera.set_zoom(0.0);
+        assert_eq!(camera.zoom(), Camera::MIN_ZOOM);
+        camera.set_zoom(-3.0);
+        assert_eq!(camera.zoom(), Camera::MIN_ZOOM);
+    }
+
+    #[test]
+    fn pan_screen_moves_by_screen_pixels() {
+        let mut camera = camera();
+        let world = [10.0, 20.0];
+        let before = camera.world_to_screen(world, VIEWPORT);
+
+        camera.pan_screen([25.0, -10.0]);
+        // the camera moved so the world slides the other way by the same number of pixels
+        let after = camera.world_to_screen(world, VIEWPORT);
+        assert_near([before[0] - after[0], before[1] - after[1]], [25.0, -10.0]);
+    }
+}
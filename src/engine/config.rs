@@ -0,0 +1,131 @@
+use std::{env, path::PathBuf};
+use ash::vk;
+use log::LevelFilter;
+use super::logger::LogSink;
+
+/// startup options for the engine, read from the command line with env var fallbacks
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    /// `--gpu <name or index>` or `SNAKE_GPU`
+    pub gpu: Option<GpuOverride>,
+    /// `--list-gpus`, print every gpu and how it scored then exit. main checks this before making an `Engine`, see `Engine::list_gpus`
+    pub list_gpus: bool,
+    /// `--validation` or `SNAKE_VALIDATION=1`, turns on the khronos validation layer if its installed
+    pub validation: bool,
+    /// `--validation-level <error|warning|info|verbose>` or `SNAKE_VALIDATION_LEVEL`, quieter messages are dropped
+    pub validation_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    /// `--validation-panic` or `SNAKE_VALIDATION_PANIC=1`, mostly for tests so broken vulkan usage fails loudly
+    pub panic_on_validation_error: bool,
+    /// `--log-level <off|error|warn|info|debug|trace>` or `SNAKE_LOG`
+    pub log_level: LevelFilter,
+    /// `--log-file <path>` or `SNAKE_LOG_FILE`, stderr otherwise
+    pub log_sink: LogSink,
+    /// `--msaa <1|2|4|8|16|32|64>` or `SNAKE_MSAA`, lowered to the most the gpu can do
+    pub msaa_samples: u32
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            gpu: None,
+            list_gpus: false,
+            validation: false,
+            validation_severity: vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            panic_on_validation_error: false,
+            log_level: LevelFilter::Info,
+            log_sink: LogSink::Stderr,
+            msaa_samples: 4
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuOverride {
+    Index(usize),
+    /// case insensitive substring of the device name
+    Name(String)
+}
+
+impl EngineConfig {
+    pub fn from_env() -> EngineConfig {
+        let args = env::args().skip(1).collect::<Vec<_>>();
+        let mut config = EngineConfig::default();
+
+        if let Some(gpu) = arg_value(&args, "--gpu").or_else(|| env::var("SNAKE_GPU").ok()) {
+            config.gpu = Some(GpuOverride::parse(&gpu));
+        }
+        config.list_gpus = has_flag(&args, "--list-gpus");
+
+        config.panic_on_validation_error = has_flag(&args, "--validation-panic") || env_flag("SNAKE_VALIDATION_PANIC");
+        config.validation = has_flag(&args, "--validation") || env_flag("SNAKE_VALIDATION") || config.panic_on_validation_error;
+
+        if let Some(level) = arg_value(&args, "--validation-level").or_else(|| env::var("SNAKE_VALIDATION_LEVEL").ok()) {
+            match parse_severity(&level) {
+                Some(severity) => config.validation_severity = severity,
+                None => eprintln!("Unknown validation level {level}, expected error, warning, info or verbose")
+            }
+        }
+
+        // the logger isn't up yet so complaints go straight to stderr
+        if let Some(level) = arg_value(&args, "--log-level").or_else(|| env::var("SNAKE_LOG").ok()) {
+            match level.parse() {
+                Ok(level) => config.log_level = level,
+                Err(_) => eprintln!("Unknown log level {level}, expected off, error, warn, info, debug or trace")
+            }
+        }
+        if let Some(path) = arg_value(&args, "--log-file").or_else(|| env::var("SNAKE_LOG_FILE").ok()) {
+            config.log_sink = LogSink::File(PathBuf::from(path));
+        }
+
+        if let Some(samples) = arg_value(&args, "--msaa").or_else(|| env::var("SNAKE_MSAA").ok()) {
+            match samples.parse::<u32>() {
+                Ok(samples) if samples.is_power_of_two() && samples <= 64 => config.msaa_samples = samples,
+                _ => eprintln!("Invalid msaa sample count {samples}, expected 1, 2, 4, 8, 16, 32 or 64")
+            }
+        }
+
+        config
+    }
+}
+
+impl GpuOverride {
+    pub fn parse(value: &str) -> GpuOverride {
+        match value.parse() {
+            Ok(index) => GpuOverride::Index(index),
+            Err(_) => GpuOverride::Name(value.to_string())
+        }
+    }
+}
+
+fn parse_severity(level: &str) -> Option<vk::DebugUtilsMessageSeverityFlagsEXT> {
+    match level.to_lowercase().as_str() {
+        "error" => Some(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR),
+        "warning" => Some(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING),
+        "info" => Some(vk::DebugUtilsMessageSeverityFlagsEXT::INFO),
+        "verbose" => Some(vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE),
+        _ => None
+    }
+}
+
+/// set to anything but `0` or empty
+pub fn env_flag(name: &str) -> bool {
+    env::var(name).is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+/// `--flag`
+pub fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|a| a == flag)
+}
+
+/// `--flag value` or `--flag=value`, unknown args are left alone so the game can have its own
+pub fn arg_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == flag {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(flag)
+                .and_then(|rest| rest.strip_prefix('='))
+                .map(|value| value.to_string())
+        }
+    })
+}
@@ -0,0 +1,156 @@
+use std::{borrow::Cow, ffi::{CStr, CString}, sync::Mutex};
+use ash::vk;
+use log::{debug, error, info, warn};
+
+pub const VALIDATION_LAYER: &str = "VK_LAYER_KHRONOS_validation\0";
+
+/// handed to the debug callback through its user data pointer, so it has to stay boxed while the messenger lives
+pub struct ValidationState {
+    pub min_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    pub panic_on_error: bool,
+    first_error: Mutex<Option<String>>
+}
+
+impl ValidationState {
+    pub fn new(min_severity: vk::DebugUtilsMessageSeverityFlagsEXT, panic_on_error: bool) -> ValidationState {
+        ValidationState {
+            min_severity,
+            panic_on_error,
+            first_error: Mutex::new(None)
+        }
+    }
+
+    /// panicking inside the callback would unwind into the driver, so errors get stashed and rust code checks for them
+    pub fn take_error(&self) -> Option<String> {
+        self.first_error.lock().unwrap().take()
+    }
+}
+
+/// the sdk isn't installed everywhere, so check before asking for the layer
+pub fn validation_layer_available(entry: &ash::Entry) -> bool {
+    let layers = entry.enumerate_instance_layer_properties().unwrap_or_default();
+
+    layers.iter().any(|l| unsafe {
+        CStr::from_ptr(l.layer_name.as_ptr()).to_bytes_with_nul() == VALIDATION_LAYER.as_bytes()
+    })
+}
+
+/// debug utils is turned on whenever its there, not just with validation,
+/// since tools like renderdoc read the object names and labels too
+pub fn debug_utils_available(entry: &ash::Entry) -> bool {
+    let extensions = entry.enumerate_instance_extension_properties(None).unwrap_or_default();
+
+    extensions.iter().any(|e| unsafe {
+        CStr::from_ptr(e.extension_name.as_ptr()) == ash::extensions::ext::DebugUtils::name()
+    })
+}
+
+pub fn set_object_name<T: vk::Handle>(
+    debug_utils: &ash::extensions::ext::DebugUtils,
+    device: &ash::Device,
+    handle: T,
+    name: &str
+) {
+    let name = CString::new(name).unwrap();
+
+    unsafe {
+        let name_info = vk::DebugUtilsObjectNameInfoEXT::builder()
+            .object_type(T::TYPE)
+            .object_handle(handle.as_raw())
+            .object_name(&name)
+            .build();
+
+        // a missing name isn't worth crashing over
+        let _ = debug_utils.set_debug_utils_object_name(device.handle(), &name_info);
+    }
+}
+
+pub fn begin_label(
+    debug_utils: &ash::extensions::ext::DebugUtils,
+    command_buffer: vk::CommandBuffer,
+    name: &str,
+    color: [f32; 4]
+) {
+    let name = CString::new(name).unwrap();
+
+    unsafe {
+        let label = vk::DebugUtilsLabelEXT::builder()
+            .label_name(&name)
+            .color(color)
+            .build();
+
+        debug_utils.cmd_begin_debug_utils_label(command_buffer, &label);
+    }
+}
+
+pub fn create_debug_messenger(
+    debug_utils: &ash::extensions::ext::DebugUtils,
+    state: &ValidationState
+) -> vk::DebugUtilsMessengerEXT {
+    // every severity at or above the minimum, the flag bits go up with severity
+    let severity = [
+        vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+        vk::DebugUtilsMessageSeverityFlagsEXT::INFO,
+        vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+        vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+    ]
+    .into_iter()
+    .filter(|s| s.as_raw() >= state.min_severity.as_raw())
+    .fold(vk::DebugUtilsMessageSeverityFlagsEXT::empty(), |all, s| all | s);
+
+    unsafe {
+        let create_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
+            .message_severity(severity)
+            .message_type(
+                vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
+            )
+            .pfn_user_callback(Some(vulkan_debug_callback))
+            .user_data(state as *const ValidationState as *mut std::os::raw::c_void)
+            .build();
+
+        debug_utils.create_debug_utils_messenger(&create_info, None).unwrap()
+    }
+}
+
+/// yoinked from ash examples
+unsafe extern "system" fn vulkan_debug_callback(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
+    user_data: *mut std::os::raw::c_void,
+) -> vk::Bool32 {
+    let callback_data = *p_callback_data;
+    let message_id_number = callback_data.message_id_number;
+
+    let message_id_name = if callback_data.p_message_id_name.is_null() {
+        Cow::from("")
+    } else {
+        CStr::from_ptr(callback_data.p_message_id_name).to_string_lossy()
+    };
+
+    let message = if callback_data.p_message.is_null() {
+        Cow::from("")
+    } else {
+        CStr::from_ptr(callback_data.p_message).to_string_lossy()
+    };
+
+    let message_type = format!("{message_type:?}");
+    let message_id_name = message_id_name.as_ref();
+    match message_severity {
+        vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => error!(target: "vulkan", kind = message_type, id = message_id_name, number = message_id_number; "{message}"),
+        vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => warn!(target: "vulkan", kind = message_type, id = message_id_name, number = message_id_number; "{message}"),
+        vk::DebugUtilsMessageSeverityFlagsEXT::INFO => info!(target: "vulkan", kind = message_type, id = message_id_name, number = message_id_number; "{message}"),
+        _ => debug!(target: "vulkan", kind = message_type, id = message_id_name, number = message_id_number; "{message}")
+    }
+
+    if message_severity == vk::DebugUtilsMessageSeverityFlagsEXT::ERROR && !user_data.is_null() {
+        let state = &*(user_data as *const ValidationState);
+        if state.panic_on_error {
+            state.first_error.lock().unwrap().get_or_insert_with(|| format!("[{message_id_name}] {message}"));
+        }
+    }
+
+    vk::FALSE
+}
@@ -1,22 +1,53 @@
 use ash::vk;
-use super::Engine;
+use super::{projection::Mat4, Engine};
 
 #[repr(C)]
+#[derive(Debug, Clone, Copy)]
 pub struct UBO {
-    pub color: [f32; 3]
+    /// straight rgba, the shader premultiplies it
+    pub color: [f32; 4]
 }
 
-#[derive(Debug, Clone)]
+/// every pipeline's push constants, layout has to match the `Push` block in the shaders.
+/// `projection` is pushed once per frame for the camera and again for the hud, `rect` for every rect
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PushConstants {
+    pub projection: Mat4,
+    pub rect: RectPush
+}
+
+/// the rect and sprite shaders' part of `PushConstants`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RectPush {
+    /// x, y, width, height in pixels from the top left
+    pub rect: [f32; 4],
+    /// u, v, width, height in 0..1, only the sprite shaders use it
+    pub uv_rect: [f32; 4],
+    /// straight rgba, the shader premultiplies it
+    pub color: [f32; 4]
+}
+
+impl PushConstants {
+    /// where `rect` starts
+    pub const RECT_OFFSET: u32 = std::mem::size_of::<Mat4>() as u32;
+}
+
+/// a uniform buffer descriptor set in its own pool.
+/// not `Clone` on purpose, dropping it destroys the pool so only drop it once the gpu is done with the frame that used it
 pub struct DescriptorSet {
     descriptor_pool: vk::DescriptorPool,
     descriptor_set: vk::DescriptorSet,
-    buffer_info: vk::DescriptorBufferInfo
+    buffer_info: vk::DescriptorBufferInfo,
+    device: ash::Device
 }
 
 pub struct DescriptorBuilder {
     bindings: Vec<vk::DescriptorSetLayoutBinding>,
     uniform: Option<vk::Buffer>,
-    uniform_size: Option<u64>
+    uniform_size: Option<u64>,
+    name: Option<String>
 }
 
 impl DescriptorBuilder {
@@ -31,6 +62,12 @@ impl DescriptorBuilder {
         self
     }
 
+    /// debug name for the set and its pool
+    pub fn name(mut self, name: &str) -> DescriptorBuilder {
+        self.name = Some(name.to_string());
+        self
+    }
+
     pub fn build(self, engine: &Engine) -> DescriptorSet {
         unsafe {
             let descriptor_pool = {
@@ -61,6 +98,11 @@ impl DescriptorBuilder {
                 engine.device().allocate_descriptor_sets(&create_info).unwrap()[0]
             };
 
+            if let Some(name) = &self.name {
+                engine.name_object(descriptor_pool, &format!("{name} pool"));
+                engine.name_object(descriptor_set, name);
+            }
+
             let buffer_info = vk::DescriptorBufferInfo::builder()
                 .buffer(self.uniform.unwrap())
                 .offset(0)
@@ -70,7 +112,8 @@ impl DescriptorBuilder {
             DescriptorSet {
                 descriptor_pool,
                 descriptor_set,
-                buffer_info
+                buffer_info,
+                device: engine.device()
             }
         }
     }
@@ -81,7 +124,8 @@ impl DescriptorSet {
         DescriptorBuilder {
             bindings: Vec::new(),
             uniform: None,
-            uniform_size: None
+            uniform_size: None,
+            name: None
         }
     }
 
@@ -103,3 +147,12 @@ impl DescriptorSet {
         self.descriptor_set
     }
 }
+
+impl Drop for DescriptorSet {
+    fn drop(&mut self) {
+        unsafe {
+            // frees the set too
+            self.device.destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+    }
+}
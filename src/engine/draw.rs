@@ -0,0 +1,77 @@
+use ash::vk;
+use super::{pipeline::PipelineSettings, texture::Sprite};
+
+/// what gets drawn on top of what, later layers cover earlier ones.
+/// draws in the same layer keep the order they were submitted in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    Background,
+    Board,
+    Snake,
+    Effects,
+    Hud
+}
+
+impl Layer {
+    pub const ALL: [Layer; 5] = [
+        Layer::Background,
+        Layer::Board,
+        Layer::Snake,
+        Layer::Effects,
+        Layer::Hud
+    ];
+
+    /// name of the debug label the layer is recorded under
+    pub fn label(&self) -> &'static str {
+        match self {
+            Layer::Background => "background",
+            Layer::Board => "board",
+            Layer::Snake => "snake",
+            Layer::Effects => "effects",
+            Layer::Hud => "hud"
+        }
+    }
+
+    /// colour of the debug label in frame captures
+    pub fn label_color(&self) -> [f32; 4] {
+        match self {
+            Layer::Background => [0.3, 0.3, 0.3, 1.0],
+            Layer::Board => [0.2, 0.6, 0.2, 1.0],
+            Layer::Snake => [0.2, 0.2, 0.8, 1.0],
+            Layer::Effects => [0.8, 0.4, 0.8, 1.0],
+            Layer::Hud => [0.8, 0.8, 0.2, 1.0]
+        }
+    }
+
+    /// drawn straight in window pixels instead of through the camera
+    pub fn screen_space(&self) -> bool {
+        *self == Layer::Hud
+    }
+}
+
+/// one draw waiting to be recorded at the end of the frame.
+/// buffers and sets are only borrowed by handle so they have to outlive the frame
+#[derive(Debug, Clone, Copy)]
+pub enum DrawCommand {
+    /// a vertex buffer drawn with its own descriptor set
+    Mesh {
+        pipeline: PipelineSettings,
+        vertex_buffer: vk::Buffer,
+        vertex_count: u32,
+        descriptor_set: Option<vk::DescriptorSet>
+    },
+    /// a flat rect in pixels from the top left, drawn with `PipelineSettings::RECT`.
+    /// pixels are world pixels seen through the camera unless the layer is in screen space
+    Rect {
+        position: [f32; 2],
+        size: [f32; 2],
+        color: [f32; 4]
+    },
+    /// a textured rect, `color` tints the texture, drawn with `PipelineSettings::SPRITE`
+    Sprite {
+        sprite: Sprite,
+        position: [f32; 2],
+        size: [f32; 2],
+        color: [f32; 4]
+    }
+}
@@ -0,0 +1,184 @@
+use std::time::{Duration, Instant};
+use super::Engine;
+
+/// runs the simulation at a fixed rate and renders as fast as the swapchain lets it,
+/// leftover time is handed to the renderer as a 0..1 fraction to interpolate with
+pub struct GameLoop {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+    paused: bool
+}
+
+impl GameLoop {
+    /// catching up on more than this many steps in one frame just makes the next frame later
+    pub const DEFAULT_MAX_STEPS: u32 = 5;
+
+    /// `tick_rate` is simulation steps per second
+    pub fn new(tick_rate: f32) -> GameLoop {
+        GameLoop {
+            step: Duration::from_secs_f32(1.0 / tick_rate),
+            max_steps: GameLoop::DEFAULT_MAX_STEPS,
+            accumulator: Duration::ZERO,
+            paused: false
+        }
+    }
+
+    /// the most steps run in a single frame, anything past that gets dropped instead of piling up
+    pub fn max_steps(mut self, max_steps: u32) -> GameLoop {
+        assert!(max_steps > 0, "The game loop has to be able to step at least once a frame");
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// `update` gets called once per step with the time of one step, `render` once per frame between `begin_draw` and `end_draw`.
+    /// both get the loop back so they can pause it or change the tick rate
+    pub fn run<S>(
+        &mut self,
+        engine: &mut Engine,
+        state: &mut S,
+        mut update: impl FnMut(&mut S, &mut Engine, &mut GameLoop),
+        mut render: impl FnMut(&mut S, &mut Engine, &mut GameLoop)
+    ) {
+        let mut prev_time = Instant::now();
+        while engine.running() {
+            let crnt_time = Instant::now();
+            let frame_time = crnt_time - prev_time;
+            prev_time = crnt_time;
+
+            self.advance(frame_time, |game_loop| update(state, engine, game_loop));
+
+            engine.begin_draw();
+            render(state, engine, self);
+            engine.end_draw();
+        }
+    }
+
+    /// adds `frame_time` and runs `step` for every whole step that fits, returns how many ran.
+    /// past `max_steps` the rest is dropped, `step` can pause the loop or change the tick rate part way through
+    pub fn advance(&mut self, frame_time: Duration, mut step: impl FnMut(&mut GameLoop)) -> u32 {
+        // time spent paused never reaches the accumulator so nothing jumps on unpause
+        if self.paused {
+            return 0;
+        }
+        self.accumulator += frame_time;
+
+        let mut steps = 0;
+        while !self.paused && self.accumulator >= self.step {
+            if steps == self.max_steps {
+                log::debug!(
+                    dropped_ms = (self.accumulator - self.accumulator.min(self.step)).as_secs_f32() * 1000.0;
+                    "Game loop fell behind"
+                );
+                // keep what's left of the current step so interpolation doesn't snap
+                self.accumulator = Duration::from_secs_f64(self.accumulator.as_secs_f64() % self.step.as_secs_f64());
+                break;
+            }
+
+            // the step can change the tick rate, take off the one it actually ran
+            let step_time = self.step;
+            step(self);
+            self.accumulator = self.accumulator.saturating_sub(step_time);
+            steps += 1;
+        }
+
+        steps
+    }
+
+    /// how far into the next step we are, 0 to 1
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f32() / self.step.as_secs_f32()).min(1.0)
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// takes effect from the next step, time already built up carries over
+    pub fn set_tick_rate(&mut self, tick_rate: f32) {
+        self.step = Duration::from_secs_f32(1.0 / tick_rate);
+    }
+
+    /// throws away any time built up towards the next step, for starting something new so
+    /// time left over from before doesn't run as an extra step straight away
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// while paused `update` isn't called and `alpha` stays where it was, `render` keeps going
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn steps_once_per_whole_step() {
+        let mut game_loop = GameLoop::new(10.0);
+
+        assert_eq!(game_loop.advance(ms(50), |_| {}), 0);
+        assert_eq!(game_loop.advance(ms(60), |_| {}), 1);
+        assert_eq!(game_loop.advance(ms(200), |_| {}), 2);
+    }
+
+    #[test]
+    fn leftover_time_becomes_alpha() {
+        let mut game_loop = GameLoop::new(10.0);
+
+        game_loop.advance(ms(125), |_| {});
+        assert!((game_loop.alpha() - 0.25).abs() < 1e-3, "alpha was {}", game_loop.alpha());
+    }
+
+    #[test]
+    fn falling_behind_caps_the_steps_and_drops_the_rest() {
+        let mut game_loop = GameLoop::new(10.0).max_steps(3);
+
+        let mut ran = 0;
+        assert_eq!(game_loop.advance(ms(1030), |_| ran += 1), 3);
+        assert_eq!(ran, 3);
+        // only the part of a step survives, not the 7 steps that didn't run
+        assert!((game_loop.alpha() - 0.3).abs() < 1e-3, "alpha was {}", game_loop.alpha());
+        assert_eq!(game_loop.advance(Duration::ZERO, |_| {}), 0);
+    }
+
+    #[test]
+    fn paused_never_steps() {
+        let mut game_loop = GameLoop::new(10.0);
+        game_loop.set_paused(true);
+
+        assert_eq!(game_loop.advance(ms(1000), |_| {}), 0);
+        assert_eq!(game_loop.alpha(), 0.0);
+
+        // and the time paused doesn't pile up for later
+        game_loop.set_paused(false);
+        assert_eq!(game_loop.advance(Duration::ZERO, |_| {}), 0);
+    }
+
+    #[test]
+    fn pausing_from_a_step_stops_the_rest() {
+        let mut game_loop = GameLoop::new(10.0);
+
+        assert_eq!(game_loop.advance(ms(450), |game_loop| game_loop.set_paused(true)), 1);
+    }
+
+    #[test]
+    fn reset_drops_leftover_time() {
+        let mut game_loop = GameLoop::new(10.0);
+        game_loop.accumulator = Duration::from_millis(80);
+        assert!(game_loop.alpha() > 0.5);
+
+        game_loop.reset();
+        assert_eq!(game_loop.alpha(), 0.0);
+    }
+}
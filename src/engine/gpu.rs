@@ -0,0 +1,183 @@
+use std::ffi::CStr;
+use ash::vk;
+use log::warn;
+use super::config::GpuOverride;
+
+pub const REQUIRED_DEVICE_EXTENSIONS: [&CStr; 1] = [
+    ash::extensions::khr::Swapchain::name()
+];
+
+/// a physical device and how well it suits us
+pub struct GpuCandidate {
+    pub gpu: vk::PhysicalDevice,
+    pub index: usize,
+    pub name: String,
+    pub device_type: vk::PhysicalDeviceType,
+    /// `Err` says why the gpu can't be used at all
+    pub score: Result<u32, String>
+}
+
+/// uploads go through the graphics queue too, a separate transfer queue would need ownership transfers for every staging copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFamilies {
+    pub graphics: u32,
+    pub present: u32
+}
+
+impl QueueFamilies {
+    pub fn find(
+        instance: &ash::Instance,
+        surface_util: &ash::extensions::khr::Surface,
+        surface_khr: vk::SurfaceKHR,
+        gpu: vk::PhysicalDevice
+    ) -> Result<QueueFamilies, String> {
+        unsafe {
+            let families = instance.get_physical_device_queue_family_properties(gpu);
+            let can_present = |index: u32| surface_util.get_physical_device_surface_support(gpu, index, surface_khr).unwrap_or(false);
+
+            let graphics_families = (0..families.len() as u32)
+                .filter(|i| families[*i as usize].queue_flags.contains(vk::QueueFlags::GRAPHICS))
+                .collect::<Vec<_>>();
+            if graphics_families.is_empty() {
+                return Err("no graphics queue family".to_string());
+            }
+
+            // one family doing both saves ownership juggling between queues
+            let (graphics, present) = match graphics_families.iter().find(|i| can_present(**i)) {
+                Some(family) => (*family, *family),
+                None => {
+                    let present = (0..families.len() as u32)
+                        .find(|i| can_present(*i))
+                        .ok_or("no queue family can present to the window surface")?;
+
+                    (graphics_families[0], present)
+                }
+            };
+
+            Ok(QueueFamilies {
+                graphics,
+                present
+            })
+        }
+    }
+
+    /// every family we use once, for `SharingMode::CONCURRENT`
+    pub fn unique(&self) -> Vec<u32> {
+        if self.graphics == self.present {
+            vec![self.graphics]
+        } else {
+            vec![self.graphics, self.present]
+        }
+    }
+}
+
+/// `surface` is `None` for `--list-gpus`, which runs before there's a window to present to
+pub fn rate_gpus(
+    instance: &ash::Instance,
+    surface: Option<(&ash::extensions::khr::Surface, vk::SurfaceKHR)>
+) -> Vec<GpuCandidate> {
+    unsafe {
+        instance
+            .enumerate_physical_devices()
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .map(|(index, gpu)| {
+                let properties = instance.get_physical_device_properties(gpu);
+
+                GpuCandidate {
+                    gpu,
+                    index,
+                    name: CStr::from_ptr(properties.device_name.as_ptr()).to_string_lossy().into_owned(),
+                    device_type: properties.device_type,
+                    score: score_gpu(instance, surface, gpu, &properties)
+                }
+            })
+            .collect()
+    }
+}
+
+/// picks the override if there is one and its usable, otherwise the best scoring gpu
+pub fn pick_gpu<'a>(candidates: &'a [GpuCandidate], gpu_override: Option<&GpuOverride>) -> &'a GpuCandidate {
+    if let Some(gpu_override) = gpu_override {
+        let chosen = candidates.iter().find(|c| match gpu_override {
+            GpuOverride::Index(index) => c.index == *index,
+            GpuOverride::Name(name) => c.name.to_lowercase().contains(&name.to_lowercase())
+        });
+
+        match chosen {
+            Some(candidate) if candidate.score.is_ok() => return candidate,
+            Some(candidate) => warn!(
+                "Requested gpu {} can't be used ({}), picking one instead",
+                candidate.name,
+                candidate.score.as_ref().unwrap_err()
+            ),
+            None => warn!("No gpu matches {gpu_override:?}, picking one instead")
+        }
+    }
+
+    candidates
+        .iter()
+        .filter(|c| c.score.is_ok())
+        .max_by_key(|c| *c.score.as_ref().unwrap())
+        .expect("No usable gpu found, run with --list-gpus to see why")
+}
+
+/// output for `--list-gpus`, so this goes to stdout rather than the log
+pub fn print_gpus(candidates: &[GpuCandidate]) {
+    for candidate in candidates {
+        match &candidate.score {
+            Ok(score) => println!("[{}] {} ({:?}) score {score}", candidate.index, candidate.name, candidate.device_type),
+            Err(reason) => println!("[{}] {} ({:?}) unusable: {reason}", candidate.index, candidate.name, candidate.device_type)
+        }
+    }
+}
+
+fn score_gpu(
+    instance: &ash::Instance,
+    surface: Option<(&ash::extensions::khr::Surface, vk::SurfaceKHR)>,
+    gpu: vk::PhysicalDevice,
+    properties: &vk::PhysicalDeviceProperties
+) -> Result<u32, String> {
+    unsafe {
+        let extensions = instance.enumerate_device_extension_properties(gpu).map_err(|e| e.to_string())?;
+        for required in REQUIRED_DEVICE_EXTENSIONS {
+            let supported = extensions.iter().any(|e| CStr::from_ptr(e.extension_name.as_ptr()) == required);
+            if !supported {
+                return Err(format!("missing {}", required.to_string_lossy()));
+            }
+        }
+
+        match surface {
+            Some((surface_util, surface_khr)) => {
+                QueueFamilies::find(instance, surface_util, surface_khr, gpu)?;
+
+                let formats = surface_util.get_physical_device_surface_formats(gpu, surface_khr).unwrap_or_default();
+                let present_modes = surface_util.get_physical_device_surface_present_modes(gpu, surface_khr).unwrap_or_default();
+                if formats.is_empty() || present_modes.is_empty() {
+                    return Err("no surface formats or present modes".to_string());
+                }
+            }
+            // can't check presenting without a window, graphics is the best we can do
+            None => {
+                let has_graphics = instance.get_physical_device_queue_family_properties(gpu)
+                    .iter()
+                    .any(|f| f.queue_flags.contains(vk::QueueFlags::GRAPHICS));
+                if !has_graphics {
+                    return Err("no graphics queue family".to_string());
+                }
+            }
+        }
+
+        let type_score = match properties.device_type {
+            vk::PhysicalDeviceType::DISCRETE_GPU => 1000,
+            vk::PhysicalDeviceType::INTEGRATED_GPU => 500,
+            vk::PhysicalDeviceType::VIRTUAL_GPU => 200,
+            vk::PhysicalDeviceType::CPU => 100,
+            _ => 50
+        };
+
+        // tie breaker between two gpus of the same type
+        Ok(type_score + properties.limits.max_image_dimension2_d / 1024)
+    }
+}
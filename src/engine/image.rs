@@ -0,0 +1,232 @@
+use std::{fmt, fs, io, path::Path};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+const QOI_MAGIC: [u8; 4] = *b"qoif";
+/// same limit as the reference decoder
+const MAX_QOI_PIXELS: usize = 400_000_000;
+
+/// decoded pixels, always straight alpha rgba8 with the top row first
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    Png(png::DecodingError),
+    Qoi(&'static str),
+    UnknownFormat
+}
+
+impl Image {
+    /// reads a png or qoi file, the format is worked out from the first few bytes
+    pub fn load(path: &Path) -> Result<Image, ImageError> {
+        Image::decode(&fs::read(path).map_err(ImageError::Io)?)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Image, ImageError> {
+        if bytes.starts_with(&PNG_SIGNATURE) {
+            decode_png(bytes)
+        } else if bytes.starts_with(&QOI_MAGIC) {
+            decode_qoi(bytes)
+        } else {
+            Err(ImageError::UnknownFormat)
+        }
+    }
+}
+
+fn decode_png(bytes: &[u8]) -> Result<Image, ImageError> {
+    let mut decoder = png::Decoder::new(bytes);
+    // palettes and low bit depths come out as 8 bit, 16 bit gets cut down to 8
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+    let mut reader = decoder.read_info().map_err(ImageError::Png)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(ImageError::Png)?;
+    buffer.truncate(info.buffer_size());
+
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => buffer.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|p| [*p, *p, *p, 255]).collect(),
+        png::ColorType::Indexed => unreachable!("EXPAND turns palettes into rgb")
+    };
+
+    Ok(Image {
+        width: info.width,
+        height: info.height,
+        pixels
+    })
+}
+
+/// https://qoiformat.org/qoi-specification.pdf
+fn decode_qoi(bytes: &[u8]) -> Result<Image, ImageError> {
+    const HEADER_SIZE: usize = 14;
+    const OP_RGB: u8 = 0xfe;
+    const OP_RGBA: u8 = 0xff;
+    const OP_INDEX: u8 = 0b00;
+    const OP_DIFF: u8 = 0b01;
+    const OP_LUMA: u8 = 0b10;
+
+    if bytes.len() < HEADER_SIZE {
+        return Err(ImageError::Qoi("file is shorter than the header"));
+    }
+
+    let width = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    let height = u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+    if width == 0 || height == 0 {
+        return Err(ImageError::Qoi("image is empty"));
+    }
+    // the channel count and colour space bytes only describe the source, the data is always rgba
+
+    // the header is untrusted, don't let it ask for more memory than any real image needs
+    let byte_count = (width as usize).checked_mul(height as usize)
+        .filter(|count| *count <= MAX_QOI_PIXELS)
+        .and_then(|count| count.checked_mul(4))
+        .ok_or(ImageError::Qoi("image is too big"))?;
+    let mut pixels = Vec::with_capacity(byte_count);
+    let mut index = [[0u8; 4]; 64];
+    let mut pixel = [0u8, 0, 0, 255];
+    let mut run = 0;
+    let mut position = HEADER_SIZE;
+
+    let next = |position: &mut usize| -> Result<u8, ImageError> {
+        let byte = *bytes.get(*position).ok_or(ImageError::Qoi("data ends before the last pixel"))?;
+        *position += 1;
+        Ok(byte)
+    };
+
+    while pixels.len() < byte_count {
+        if run > 0 {
+            run -= 1;
+        } else {
+            let op = next(&mut position)?;
+
+            if op == OP_RGB {
+                pixel[0] = next(&mut position)?;
+                pixel[1] = next(&mut position)?;
+                pixel[2] = next(&mut position)?;
+            } else if op == OP_RGBA {
+                pixel[0] = next(&mut position)?;
+                pixel[1] = next(&mut position)?;
+                pixel[2] = next(&mut position)?;
+                pixel[3] = next(&mut position)?;
+            } else {
+                match op >> 6 {
+                    OP_INDEX => pixel = index[(op & 0x3f) as usize],
+                    OP_DIFF => {
+                        pixel[0] = pixel[0].wrapping_add((op >> 4) & 0x03).wrapping_sub(2);
+                        pixel[1] = pixel[1].wrapping_add((op >> 2) & 0x03).wrapping_sub(2);
+                        pixel[2] = pixel[2].wrapping_add(op & 0x03).wrapping_sub(2);
+                    }
+                    OP_LUMA => {
+                        let second = next(&mut position)?;
+                        let dg = (op & 0x3f).wrapping_sub(32);
+                        pixel[0] = pixel[0].wrapping_add(dg).wrapping_add(second >> 4).wrapping_sub(8);
+                        pixel[1] = pixel[1].wrapping_add(dg);
+                        pixel[2] = pixel[2].wrapping_add(dg).wrapping_add(second & 0x0f).wrapping_sub(8);
+                    }
+                    // OP_RUN, the stored value is one less than the length and this pixel is the first
+                    _ => run = op & 0x3f
+                }
+            }
+
+            let hash = (pixel[0] as usize * 3 + pixel[1] as usize * 5 + pixel[2] as usize * 7 + pixel[3] as usize * 11) % 64;
+            index[hash] = pixel;
+        }
+
+        pixels.extend_from_slice(&pixel);
+    }
+
+    Ok(Image {
+        width,
+        height,
+        pixels
+    })
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io(e) => write!(f, "{e}"),
+            ImageError::Png(e) => write!(f, "bad png: {e}"),
+            ImageError::Qoi(e) => write!(f, "bad qoi: {e}"),
+            ImageError::UnknownFormat => write!(f, "not a png or qoi file")
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    fn qoi_header(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = QOI_MAGIC.to_vec();
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.extend_from_slice(&[4, 0]);
+        bytes
+    }
+
+    /// 2x2, red red / blue red
+    fn tiny_qoi() -> Vec<u8> {
+        let mut bytes = qoi_header(2, 2);
+        bytes.extend_from_slice(&[
+            0xff, 255, 0, 0, 255, // rgba, red
+            0xc0, // run of 1
+            0xfe, 0, 0, 255, // rgb, blue
+            50 // index, red hashes to 50
+        ]);
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        bytes
+    }
+
+    #[test]
+    fn decodes_qoi() {
+        let image = Image::decode(&tiny_qoi()).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.pixels, [RED, RED, BLUE, RED].concat());
+    }
+
+    #[test]
+    fn truncated_qoi_is_an_error() {
+        let bytes = tiny_qoi();
+
+        assert!(matches!(Image::decode(&bytes[..bytes.len() - 9]), Err(ImageError::Qoi(_))));
+    }
+
+    #[test]
+    fn huge_qoi_is_an_error() {
+        for (width, height) in [(u32::MAX, u32::MAX), (20_000, 20_001)] {
+            assert!(matches!(Image::decode(&qoi_header(width, height)), Err(ImageError::Qoi("image is too big"))));
+        }
+    }
+
+    #[test]
+    fn empty_qoi_is_an_error() {
+        assert!(matches!(Image::decode(&qoi_header(0, 4)), Err(ImageError::Qoi(_))));
+    }
+
+    #[test]
+    fn decodes_png() {
+        let image = Image::decode(include_bytes!("../../assets/sprites/snake.png")).unwrap();
+
+        assert_eq!((image.width, image.height), (96, 16));
+        assert_eq!(image.pixels.len(), 96 * 16 * 4);
+    }
+
+    #[test]
+    fn unknown_format() {
+        assert!(matches!(Image::decode(b"GIF89a"), Err(ImageError::UnknownFormat)));
+    }
+}
@@ -0,0 +1,79 @@
+use std::{fs::File, io::{self, Write}, path::PathBuf, sync::Mutex, time::Instant};
+use log::{kv, LevelFilter, Log, Metadata, Record};
+
+/// where log lines end up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogSink {
+    Stderr,
+    File(PathBuf)
+}
+
+/// prints `[   1.234s INFO  target] message key=value ...` lines to the sink
+struct Logger {
+    level: LevelFilter,
+    start: Instant,
+    output: Mutex<Box<dyn Write + Send>>
+}
+
+/// installs the logger for the `log` macros, only the first call does anything
+pub fn init(level: LevelFilter, sink: &LogSink) {
+    let output: Box<dyn Write + Send> = match sink {
+        LogSink::Stderr => Box::new(io::stderr()),
+        LogSink::File(path) => match File::create(path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                eprintln!("Failed to open log file {}: {e}, logging to stderr", path.display());
+                Box::new(io::stderr())
+            }
+        }
+    };
+
+    let logger = Logger {
+        level,
+        start: Instant::now(),
+        output: Mutex::new(output)
+    };
+
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut line = format!(
+            "[{:>8.3}s {:<5} {}] {}",
+            self.start.elapsed().as_secs_f32(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        let mut fields = KeyValues(&mut line);
+        let _ = record.key_values().visit(&mut fields);
+
+        let mut output = self.output.lock().unwrap();
+        let _ = writeln!(output, "{line}");
+    }
+
+    fn flush(&self) {
+        let _ = self.output.lock().unwrap().flush();
+    }
+}
+
+struct KeyValues<'a>(&'a mut String);
+
+impl<'kvs> kv::VisitSource<'kvs> for KeyValues<'_> {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        self.0.push_str(&format!(" {key}={value}"));
+        Ok(())
+    }
+}
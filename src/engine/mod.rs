@@ -1,25 +1,54 @@
 pub mod vertex;
 pub mod buffer;
+pub mod camera;
 pub mod descriptor;
-
-use std::{ffi::{CString, CStr}, ptr::null};
+pub mod draw;
+pub mod game_loop;
+pub mod allocator;
+pub mod shader;
+pub mod pipeline;
+pub mod pipeline_cache;
+pub mod projection;
+pub mod paths;
+pub mod config;
+pub mod gpu;
+pub mod debug;
+pub mod logger;
+pub mod image;
+pub mod sprite_sheet;
+pub mod msaa;
+pub mod stats;
+pub mod texture;
+pub mod text;
+
+use std::{cell::RefCell, collections::HashMap, ffi::CString, mem::size_of, ptr::null, rc::Rc, sync::mpsc::Receiver, time::Instant};
 use ash::vk;
-use glfw::Window;
+use glfw::{Action, GamepadState, JoystickId, Key, Window, WindowEvent};
+use log::{debug, error, info, warn};
 use winapi::um::libloaderapi::GetModuleHandleW;
-use self::vertex::Vertex;
+use self::{allocator::{Allocator, SharedAllocator}, buffer::Buffer, config::EngineConfig, camera::Camera, descriptor::{PushConstants, RectPush}, draw::{DrawCommand, Layer}, debug::{begin_label, create_debug_messenger, debug_utils_available, set_object_name, validation_layer_available, ValidationState, VALIDATION_LAYER}, gpu::{pick_gpu, print_gpus, rate_gpus, QueueFamilies, REQUIRED_DEVICE_EXTENSIONS}, msaa::{pick_sample_count, MsaaTarget}, pipeline::PipelineSettings, pipeline_cache::{load_pipeline_cache, save_pipeline_cache}, projection::{ortho, Mat4}, shader::ShaderWatcher, stats::{FrameStats, FrameTimings, GpuTimer}, text::text_runs, texture::Sprite, vertex::Vertex};
 
 pub struct Engine {
     glfw: glfw::Glfw,
     window: glfw::Window,
+    events: Receiver<(f64, WindowEvent)>,
+    /// whatever glfw sent since the last `running`
+    window_events: Vec<WindowEvent>,
     entry: ash::Entry,
     // vulkan
     instance: ash::Instance,
     gpu: vk::PhysicalDevice,
+    gpu_features: vk::PhysicalDeviceFeatures,
+    gpu_properties: vk::PhysicalDeviceProperties,
     memory_properties: vk::PhysicalDeviceMemoryProperties,
     device: ash::Device,
-    device_queue: vk::Queue,
+    queue_families: QueueFamilies,
+    graphics_queue: vk::Queue,
+    present_queue: vk::Queue,
+    allocator: SharedAllocator,
     debug_utils: Option<ash::extensions::ext::DebugUtils>,
     debug_messenger: Option<vk::DebugUtilsMessengerEXT>,
+    validation_state: Box<ValidationState>,
     surface_khr: vk::SurfaceKHR,
     surface_util: ash::extensions::khr::Surface,
     swapchain: vk::SwapchainKHR,
@@ -31,49 +60,110 @@ pub struct Engine {
     scissor: vk::Rect2D,
     swapchain_image_views: Vec<vk::ImageView>,
     swapchain_framebuffers: Vec<vk::Framebuffer>,
+    samples: vk::SampleCountFlags,
+    /// `None` without msaa, the swapchain image is drawn to directly then
+    msaa_target: Option<MsaaTarget>,
     render_pass: vk::RenderPass,
     pipeline_layout: vk::PipelineLayout,
-    graphics_pipeline: vk::Pipeline,
+    pipelines: HashMap<PipelineSettings, vk::Pipeline>,
+    pipeline_cache: vk::PipelineCache,
+    shader_watcher: ShaderWatcher,
     command_pool: vk::CommandPool,
     descriptor_set_layout: vk::DescriptorSetLayout,
+    /// set 1, a sampled image and a sampler for sprites
+    texture_set_layout: vk::DescriptorSetLayout,
     // drawing
     draw_command_buffer: vk::CommandBuffer,
     image_available_semaphore: vk::Semaphore,
     render_finished_semaphore: vk::Semaphore,
     in_flight_fence: vk::Fence,
-    image_index: u32
+    image_index: u32,
+    /// nothing to draw to this frame, the window is minimized or the swapchain is out of date
+    skip_frame: bool,
+    /// skips rebinding the same pipeline over and over, reset every frame
+    bound_pipeline: Option<vk::Pipeline>,
+    /// everything submitted this frame, sorted by layer and recorded in `end_draw`
+    draw_list: Vec<(Layer, DrawCommand)>,
+    /// 0,0 to 1,1, scaled and moved by the rect shaders
+    rect_quad: Buffer<Vertex>,
+    camera: Camera,
+    // frame timing
+    gpu_timer: Option<GpuTimer>,
+    frame_stats: FrameStats,
+    timings: FrameTimings,
+    frame_start: Instant,
+    record_start: Instant,
+    last_report: Instant,
+    show_overlay: bool,
+    overlay_key_down: bool,
+    clear_color: [f32; 4],
+    /// text and background of the timing overlay
+    hud_colors: ([f32; 4], [f32; 4])
 }
 
 impl Engine {
+    /// the size the window asks for, see `viewport_size` for what it actually got
     pub const WIDTH: u32 = 800;
     pub const HEIGHT: u32 = 800;
     pub const TITLE: &'static str = "Vulkan Snake in Rust";
 
-    pub const DEBUG: bool = false;
+    /// seconds between frame timing log lines
+    const TIMING_REPORT_INTERVAL: f32 = 5.0;
+    /// toggles the frame timing overlay
+    const OVERLAY_KEY: Key = Key::F3;
 
-    pub fn new() -> Engine {
-        let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
-        glfw.window_hint(glfw::WindowHint::ClientApi(glfw::ClientApiHint::NoApi));
+    /// prints every gpu vulkan can see and how it scored, for `--list-gpus`.
+    /// doesn't make a window so whether a gpu can present isn't checked
+    pub fn list_gpus() {
+        unsafe {
+            let entry = ash::Entry::load().unwrap();
+            let instance = entry.create_instance(&vk::InstanceCreateInfo::builder().build(), None).unwrap();
 
-        let (window, _events) = glfw.create_window(Engine::WIDTH, Engine::HEIGHT, Engine::TITLE, glfw::WindowMode::Windowed)
-            .expect("Failed to create GLFW window.");
-        if Engine::DEBUG {
-            println!("Created Window");
+            print_gpus(&rate_gpus(&instance, None));
+
+            instance.destroy_instance(None);
         }
+    }
 
-        let mut supported_extensions = glfw.get_required_instance_extensions().unwrap();
-        supported_extensions.push("VK_EXT_debug_utils".to_string());
-        let supported_extensions = supported_extensions.iter().map(|e| format!("{e}\0")).collect::<Vec<_>>();
-        let supported_extesnions_ptrs = supported_extensions.iter().map(|e| e.as_ptr() as *const i8).collect::<Vec<_>>();
+    pub fn new(config: EngineConfig) -> Engine {
+        logger::init(config.log_level, &config.log_sink);
 
-        let enabled_layers = [
-            "VK_LAYER_KHRONOS_validation\0",
-        ];
-        let enabled_layers_ptrs = enabled_layers.iter().map(|e| e.as_ptr() as *const i8).collect::<Vec<_>>();
+        let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+        glfw.window_hint(glfw::WindowHint::ClientApi(glfw::ClientApiHint::NoApi));
+        // the swapchain is only made once, resizing would need it and everything sized to it remade
+        glfw.window_hint(glfw::WindowHint::Resizable(false));
+
+        let (mut window, events) = glfw.create_window(Engine::WIDTH, Engine::HEIGHT, Engine::TITLE, glfw::WindowMode::Windowed)
+            .expect("Failed to create GLFW window.");
+        // focus and iconify for auto pause, key presses for menus so a held key only counts once
+        window.set_focus_polling(true);
+        window.set_iconify_polling(true);
+        window.set_key_polling(true);
+        debug!("Created Window");
 
         unsafe {
             let entry = ash::Entry::load().unwrap();
 
+            let has_debug_utils = debug_utils_available(&entry);
+            let validation = config.validation && has_debug_utils && validation_layer_available(&entry);
+            if config.validation && !validation {
+                warn!("Validation was requested but {} or VK_EXT_debug_utils isn't installed, running without it", VALIDATION_LAYER.trim_end_matches('\0'));
+            }
+
+            let mut supported_extensions = glfw.get_required_instance_extensions().unwrap();
+            if has_debug_utils {
+                supported_extensions.push("VK_EXT_debug_utils".to_string());
+            }
+            let supported_extensions = supported_extensions.iter().map(|e| format!("{e}\0")).collect::<Vec<_>>();
+            let supported_extesnions_ptrs = supported_extensions.iter().map(|e| e.as_ptr() as *const i8).collect::<Vec<_>>();
+
+            let enabled_layers = if validation {
+                vec![VALIDATION_LAYER]
+            } else {
+                vec![]
+            };
+            let enabled_layers_ptrs = enabled_layers.iter().map(|e| e.as_ptr() as *const i8).collect::<Vec<_>>();
+
             let instance = {
                 let c_name = CString::new(Engine::TITLE).unwrap();
 
@@ -93,114 +183,90 @@ impl Engine {
 
                 entry.create_instance(&create_info, None).unwrap()
             };
-            if Engine::DEBUG {
-                println!("Created Vulkan Instance");
-            }
+            debug!("Created Vulkan Instance");
+
+            let validation_state = Box::new(ValidationState::new(config.validation_severity, config.panic_on_validation_error));
+            let debug_utils = has_debug_utils.then(|| ash::extensions::ext::DebugUtils::new(&entry, &instance));
+            let debug_messenger = match &debug_utils {
+                Some(debug_utils) if validation => {
+                    let debug_messenger = create_debug_messenger(debug_utils, &validation_state);
+                    debug!("Created debug messenger");
+
+                    Some(debug_messenger)
+                }
+                _ => None
+            };
 
-            let (debug_utils, debug_messenger) = if Engine::DEBUG {
-                let debug_utils = ash::extensions::ext::DebugUtils::new(&entry, &instance);
-                println!("Created debug utils");
-
-                let debug_messenger = {
-                    let create_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
-                        .message_severity(
-                            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
-                            | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
-                            | vk::DebugUtilsMessageSeverityFlagsEXT::INFO
-                            | vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE
-                        )
-                        .message_type(
-                            vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
-                            | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
-                            | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
-                        )
-                        .pfn_user_callback(Some(vulkan_debug_callback))
-                        .build();
-
-                    debug_utils.create_debug_utils_messenger(&create_info, None).unwrap()
-                };
+            let surface_util = ash::extensions::khr::Surface::new(&entry, &instance);
+            let win32_surface = ash::extensions::khr::Win32Surface::new(&entry, &instance);
+            let mut surface_khr = {
+                let create_info = vk::Win32SurfaceCreateInfoKHR::builder()
+                    .hinstance(GetModuleHandleW(null()).cast())
+                    .hwnd(window.get_win32_window())
+                    .build();
 
-                (Some(debug_utils), Some(debug_messenger))
-            } else {
-                (None, None)
+                win32_surface.create_win32_surface(&create_info, None).unwrap()
             };
-
-            let gpu = instance
-                .enumerate_physical_devices()
-                .unwrap()
-                .into_iter()
-                .filter(|p| {
-                    let properties = instance.get_physical_device_properties(*p);
-
-                    properties.device_type == vk::PhysicalDeviceType::DISCRETE_GPU ||
-                    properties.device_type == vk::PhysicalDeviceType::INTEGRATED_GPU
-                })
-                .nth(0)
-                .expect("No discrete or intergrated gpu found");
-            if Engine::DEBUG {
-                let properties = instance.get_physical_device_properties(gpu);
-
-                let name = CStr::from_ptr(properties.device_name.as_ptr()).to_string_lossy();
-                println!("Found GPU: {}", name);
-                println!("GPU Type {:#?}", properties.device_type);
+            if window.create_window_surface(instance.handle(), null(), &mut surface_khr).result().is_err() {
+                panic!("Failed to create vulkan surface");
             }
+            debug!("Created Vulkan Surface");
+            
+            let gpu = {
+                let candidates = rate_gpus(&instance, Some((&surface_util, surface_khr)));
+
+                let chosen = pick_gpu(&candidates, config.gpu.as_ref());
+                info!(gpu = chosen.name.as_str(), device_type:? = chosen.device_type, index = chosen.index; "Picked gpu");
+
+                chosen.gpu
+            };
 
             let memory_properties = instance.get_physical_device_memory_properties(gpu);
 
-            let queue_familys = instance.get_physical_device_queue_family_properties(gpu);
-            let queue_family = queue_familys
-                .iter()
-                .enumerate()
-                .find(|(_, p)| {
-                    p.queue_flags.contains(vk::QueueFlags::GRAPHICS) &&
-                    p.queue_flags.contains(vk::QueueFlags::TRANSFER)
-                })
-                .expect("No graphics queue family found");
+            let queue_families = QueueFamilies::find(&instance, &surface_util, surface_khr, gpu)
+                .unwrap_or_else(|e| panic!("Can't render to the window with this gpu: {e}"));
+            debug!(
+                graphics = queue_families.graphics,
+                present = queue_families.present;
+                "Picked queue families"
+            );
+
+            let gpu_features = instance.get_physical_device_features(gpu);
+
+            // only what the engine uses, and only if the gpu has it. cpu devices don't always do anisotropy
+            let gpu_features = vk::PhysicalDeviceFeatures {
+                fill_mode_non_solid: gpu_features.fill_mode_non_solid,
+                sampler_anisotropy: gpu_features.sampler_anisotropy,
+                ..Default::default()
+            };
 
             let device = {
-                let mut physical_device_features = instance.get_physical_device_features(gpu);
-                physical_device_features.sampler_anisotropy = 1;
-                let device_extensions = [
-                    "VK_KHR_swapchain\0",
-                ];
-                let device_extensions_ptrs = device_extensions.iter().map(|e| e.as_ptr() as *const i8).collect::<Vec<_>>();
+                let physical_device_features = gpu_features;
+                let device_extensions_ptrs = REQUIRED_DEVICE_EXTENSIONS.iter().map(|e| e.as_ptr()).collect::<Vec<_>>();
+
+                let queue_priorities = [1.0];
+                let queue_create_infos = queue_families.unique().into_iter().map(|family| {
+                    vk::DeviceQueueCreateInfo::builder()
+                        .queue_family_index(family)
+                        .queue_priorities(&queue_priorities)
+                        .build()
+                }).collect::<Vec<_>>();
 
                 let create_info = vk::DeviceCreateInfo::builder()
-                    .queue_create_infos(&[
-                        vk::DeviceQueueCreateInfo::builder()
-                            .queue_family_index(queue_family.0 as u32)
-                            .queue_priorities(&[1.0])
-                            .build(),
-                    ])
+                    .queue_create_infos(&queue_create_infos)
                     .enabled_extension_names(&device_extensions_ptrs)
                     .enabled_features(&physical_device_features)
                     .build();
 
                 instance.create_device(gpu, &create_info, None).unwrap()
             };
-            if Engine::DEBUG {
-                println!("Created Vulkan Device");
-            }
+            debug!("Created Vulkan Device");
 
-            let device_queue = device.get_device_queue(queue_family.0 as u32, 0);
+            let graphics_queue = device.get_device_queue(queue_families.graphics, 0);
+            let present_queue = device.get_device_queue(queue_families.present, 0);
 
-            let surface_util = ash::extensions::khr::Surface::new(&entry, &instance);
-            let win32_surface = ash::extensions::khr::Win32Surface::new(&entry, &instance);
-            let mut surface_khr = {
-                let create_info = vk::Win32SurfaceCreateInfoKHR::builder()
-                    .hinstance(GetModuleHandleW(null()).cast())
-                    .hwnd(window.get_win32_window())
-                    .build();
+            let allocator = Rc::new(RefCell::new(Allocator::new(device.clone(), memory_properties)));
 
-                win32_surface.create_win32_surface(&create_info, None).unwrap()
-            };
-            if window.create_window_surface(instance.handle(), null(), &mut surface_khr).result().is_err() {
-                panic!("Failed to create vulkan surface");
-            }
-            if Engine::DEBUG {
-                println!("Created Vulkan Surface");
-            }
-            
             let swapchain_util = ash::extensions::khr::Swapchain::new(&instance, &device);
 
             let (swapchain, swapchain_format, swapchain_present_mode, extent) = {
@@ -212,13 +278,17 @@ impl Engine {
                     f.format == vk::Format::B8G8R8A8_SRGB &&
                     f.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
                 }).unwrap_or_else(|| {
-                    println!("Failed to find suitable format so selected the first one");
+                    warn!(format:? = formats[0].format; "Failed to find suitable format so selected the first one");
                     formats[0]
                 });
 
-                let present_mode = present_modes.into_iter().find(|p| {
-                    *p == vk::PresentModeKHR::IMMEDIATE
-                }).unwrap();
+                // uncapped if the gpu can, fifo is the only mode every device has to support
+                let present_mode = if present_modes.contains(&vk::PresentModeKHR::IMMEDIATE) {
+                    vk::PresentModeKHR::IMMEDIATE
+                } else {
+                    warn!("Immediate present mode isn't supported, falling back to fifo");
+                    vk::PresentModeKHR::FIFO
+                };
 
                 let framebuffer_size = window.get_framebuffer_size();
                 let extent = vk::Extent2D {
@@ -226,6 +296,13 @@ impl Engine {
                     height: (framebuffer_size.1 as u32).clamp(capabilities.min_image_extent.height, capabilities.max_image_extent.height),
                 };
 
+                // presenting from another family means both need to touch the images
+                let (swapchain_sharing_mode, swapchain_queue_families) = if queue_families.graphics == queue_families.present {
+                    (vk::SharingMode::EXCLUSIVE, vec![])
+                } else {
+                    (vk::SharingMode::CONCURRENT, vec![queue_families.graphics, queue_families.present])
+                };
+
                 let create_info = vk::SwapchainCreateInfoKHR::builder()
                     .surface(surface_khr)
                     .min_image_count(capabilities.min_image_count + 1)
@@ -234,7 +311,8 @@ impl Engine {
                     .image_extent(extent)
                     .image_array_layers(1)
                     .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
-                    .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+                    .image_sharing_mode(swapchain_sharing_mode)
+                    .queue_family_indices(&swapchain_queue_families)
                     .pre_transform(capabilities.current_transform)
                     .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
                     .present_mode(present_mode)
@@ -249,9 +327,13 @@ impl Engine {
                     extent
                 )
             };
-            if Engine::DEBUG {
-                println!("Created Swapchain");
-            }
+            info!(
+                format:? = swapchain_format,
+                present_mode:? = swapchain_present_mode,
+                width = extent.width,
+                height = extent.height;
+                "Created Swapchain"
+            );
 
             let swapchain_images = swapchain_util.get_swapchain_images(swapchain).unwrap();
 
@@ -277,80 +359,7 @@ impl Engine {
                 
                 device.create_image_view(&create_info, None).unwrap()
             }).collect::<Vec<_>>();
-            if Engine::DEBUG {
-                println!("Created Swapchain Image Views");
-            }
-
-            let vertex_shader_module = {
-                let binary = include_bytes!("../../shaders/default.vert.spv");
-
-                let create_info = vk::ShaderModuleCreateInfo {
-                    s_type: vk::StructureType::SHADER_MODULE_CREATE_INFO,
-                    code_size: binary.len(),
-                    p_code: binary.as_ptr() as *const u32,
-                    ..Default::default()
-                };
-
-                device.create_shader_module(&create_info, None).unwrap()
-            };
-
-            let fragment_shader_module = {
-                let binary = include_bytes!("../../shaders/default.frag.spv");
-
-                let create_info = vk::ShaderModuleCreateInfo {
-                    s_type: vk::StructureType::SHADER_MODULE_CREATE_INFO,
-                    code_size: binary.len(),
-                    p_code: binary.as_ptr() as *const u32,
-                    ..Default::default()
-                };
-
-                device.create_shader_module(&create_info, None).unwrap()
-            };
-            if Engine::DEBUG {
-                println!("Created shader modules");
-            }
-
-            let entry_point_name = CString::new("main").unwrap();
-            let shader_stages = [
-                vk::PipelineShaderStageCreateInfo::builder()
-                    .stage(vk::ShaderStageFlags::VERTEX)
-                    .module(vertex_shader_module)
-                    .name(&entry_point_name)
-                    .build(),
-                vk::PipelineShaderStageCreateInfo::builder()
-                    .stage(vk::ShaderStageFlags::FRAGMENT)
-                    .module(fragment_shader_module)
-                    .name(&entry_point_name)
-                    .build()
-            ];
-
-            let dynamic_states = [
-                vk::DynamicState::VIEWPORT,
-                vk::DynamicState::SCISSOR
-            ];
-
-            let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
-                .dynamic_states(&dynamic_states)
-                .build();
-            if Engine::DEBUG {
-                println!("Created dynamic state");
-            }
-
-            let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::builder()
-                .vertex_binding_descriptions(&[Vertex::get_binding_description()])
-                .vertex_attribute_descriptions(&Vertex::get_attribute_descriptions())
-                .build();
-            if Engine::DEBUG {
-                println!("Created vertex input info");
-            }
-
-            let input_assembly_info = vk::PipelineInputAssemblyStateCreateInfo::builder()
-                .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
-                .primitive_restart_enable(false)
-                .build();
-            if Engine::DEBUG {
-                println!("Created input assembly info");
-            }
+            debug!("Created Swapchain Image Views");
 
             let viewport = vk::Viewport {
                 x: 0.0,
@@ -366,53 +375,6 @@ impl Engine {
                 extent,
             };
 
-            let viewport_state_info = vk::PipelineViewportStateCreateInfo::builder()
-                .viewport_count(1)
-                .scissor_count(1)
-                .build();
-            if Engine::DEBUG {
-                println!("Created viewport state info");
-            }
-
-            let rasterizer_info = vk::PipelineRasterizationStateCreateInfo::builder()
-                .depth_clamp_enable(false)
-                .rasterizer_discard_enable(false)
-                .polygon_mode(vk::PolygonMode::FILL)
-                .line_width(1.0)
-                .cull_mode(vk::CullModeFlags::BACK)
-                .front_face(vk::FrontFace::CLOCKWISE)
-                .depth_bias_enable(false)
-                .depth_bias_constant_factor(0.0)
-                .depth_bias_clamp(0.0)
-                .depth_bias_slope_factor(0.0)
-                .build();
-            if Engine::DEBUG {
-                println!("Created rasterizer info");
-            }
-
-            let multisample_info = vk::PipelineMultisampleStateCreateInfo::builder()
-                .sample_shading_enable(false)
-                .rasterization_samples(vk::SampleCountFlags::TYPE_1)
-                .build();
-            if Engine::DEBUG {
-                println!("Created multisample info");
-            }
-
-            let color_blend_attachments = [
-                vk::PipelineColorBlendAttachmentState::builder()
-                    .color_write_mask(vk::ColorComponentFlags::R | vk::ColorComponentFlags::G | vk::ColorComponentFlags::B | vk::ColorComponentFlags::A)
-                    .blend_enable(false)
-                    .build()
-            ];
-
-            let color_blend_info = vk::PipelineColorBlendStateCreateInfo::builder()
-                .logic_op_enable(false)
-                .attachments(&color_blend_attachments)
-                .build();
-            if Engine::DEBUG {
-                println!("Created color blend info");
-            }
-
             let ubo_descriptor_binding = vk::DescriptorSetLayoutBinding::builder()
                 .binding(0)
                 .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
@@ -428,12 +390,42 @@ impl Engine {
                 device.create_descriptor_set_layout(&create_info, None).unwrap()
             };
 
+            let texture_set_layout = {
+                let bindings = [
+                    vk::DescriptorSetLayoutBinding::builder()
+                        .binding(0)
+                        .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
+                        .descriptor_count(1)
+                        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+                        .build(),
+                    vk::DescriptorSetLayoutBinding::builder()
+                        .binding(1)
+                        .descriptor_type(vk::DescriptorType::SAMPLER)
+                        .descriptor_count(1)
+                        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+                        .build()
+                ];
+
+                let create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+                    .bindings(&bindings)
+                    .build();
+
+                device.create_descriptor_set_layout(&create_info, None).unwrap()
+            };
+
+            let push_range = vk::PushConstantRange::builder()
+                .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+                .offset(0)
+                .size(size_of::<PushConstants>() as u32)
+                .build();
+
             let pipeline_layout = {
                 let create_info = vk::PipelineLayoutCreateInfo::builder()
                     .set_layouts(&[
-                        descriptor_set_layout
+                        descriptor_set_layout,
+                        texture_set_layout
                     ])
-                    .push_constant_ranges(&[])
+                    .push_constant_ranges(&[push_range])
                     .build();
 
                 device.create_pipeline_layout(&create_info, None).unwrap()
@@ -442,17 +434,37 @@ impl Engine {
             let command_pool = {
                 let create_info = vk::CommandPoolCreateInfo::builder()
                     .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
-                    .queue_family_index(queue_family.0 as u32)
+                    .queue_family_index(queue_families.graphics)
                     .build();
 
                 device.create_command_pool(&create_info, None).unwrap()
             };
 
+            let gpu_properties = instance.get_physical_device_properties(gpu);
+
+            let samples = pick_sample_count(&gpu_properties, config.msaa_samples);
+            let msaa_target = (samples != vk::SampleCountFlags::TYPE_1).then(|| {
+                MsaaTarget::new(&device, &allocator, swapchain_format, extent, samples)
+            });
+            info!(samples = samples.as_raw(); "Msaa");
+
             let render_pass = {
                 let attachment_description = vk::AttachmentDescription::builder()
                     .format(swapchain_format)
-                    .samples(vk::SampleCountFlags::TYPE_1)
+                    .samples(samples)
                     .load_op(vk::AttachmentLoadOp::CLEAR)
+                    .store_op(if msaa_target.is_some() { vk::AttachmentStoreOp::DONT_CARE } else { vk::AttachmentStoreOp::STORE })
+                    .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+                    .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+                    .initial_layout(vk::ImageLayout::UNDEFINED)
+                    .final_layout(if msaa_target.is_some() { vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL } else { vk::ImageLayout::PRESENT_SRC_KHR })
+                    .build();
+
+                // the swapchain image, only used with msaa
+                let resolve_attachment_description = vk::AttachmentDescription::builder()
+                    .format(swapchain_format)
+                    .samples(vk::SampleCountFlags::TYPE_1)
+                    .load_op(vk::AttachmentLoadOp::DONT_CARE)
                     .store_op(vk::AttachmentStoreOp::STORE)
                     .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
                     .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
@@ -465,11 +477,27 @@ impl Engine {
                     .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
                     .build();
 
-                let subpass_description = vk::SubpassDescription::builder()
-                    .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
-                    .color_attachments(&[color_attachment_ref])
+                let resolve_attachment_ref = vk::AttachmentReference::builder()
+                    .attachment(1)
+                    .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
                     .build();
 
+                let color_attachment_refs = [color_attachment_ref];
+                let resolve_attachment_refs = [resolve_attachment_ref];
+                let mut subpass_description = vk::SubpassDescription::builder()
+                    .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+                    .color_attachments(&color_attachment_refs);
+                if msaa_target.is_some() {
+                    subpass_description = subpass_description.resolve_attachments(&resolve_attachment_refs);
+                }
+                let subpass_description = subpass_description.build();
+
+                let attachments = if msaa_target.is_some() {
+                    vec![attachment_description, resolve_attachment_description]
+                } else {
+                    vec![attachment_description]
+                };
+
                 let subpass_dependency = vk::SubpassDependency::builder()
                     .src_subpass(vk::SUBPASS_EXTERNAL)
                     .dst_subpass(0)
@@ -480,42 +508,49 @@ impl Engine {
                     .build();
 
                 let create_info = vk::RenderPassCreateInfo::builder()
-                    .attachments(&[attachment_description])
+                    .attachments(&attachments)
                     .subpasses(&[subpass_description])
                     .dependencies(&[subpass_dependency])
                     .build();
 
                 device.create_render_pass(&create_info, None).unwrap()
             };
-            if Engine::DEBUG {
-                println!("Created render pass");
-            }
+            debug!("Created render pass");
 
-            let graphics_pipeline = {
-                let create_info = vk::GraphicsPipelineCreateInfo::builder()
-                    .stages(&shader_stages)
-                    .dynamic_state(&dynamic_state)
-                    .vertex_input_state(&vertex_input_info)
-                    .input_assembly_state(&input_assembly_info)
-                    .viewport_state(&viewport_state_info)
-                    .rasterization_state(&rasterizer_info)
-                    .multisample_state(&multisample_info)
-                    .color_blend_state(&color_blend_info)
-                    .layout(pipeline_layout)
-                    .render_pass(render_pass)
-                    .subpass(0)
-                    .build();
+            let shader_watcher = ShaderWatcher::new();
+            let pipeline_cache = load_pipeline_cache(&device, &gpu_properties);
 
-                device.create_graphics_pipelines(vk::PipelineCache::null(), &[create_info], None).unwrap()[0]
-            };
-            if Engine::DEBUG {
-                println!("Created graphics pipeline");
+            let timestamp_valid_bits = instance.get_physical_device_queue_family_properties(gpu)[queue_families.graphics as usize].timestamp_valid_bits;
+            let gpu_timer = GpuTimer::new(&device, &gpu_properties, timestamp_valid_bits);
+            if gpu_timer.is_none() {
+                info!("Graphics queue can't write timestamps, gpu frame time won't be measured");
             }
 
+            let rect_quad = Buffer::new(
+                &[
+                    Vertex::new([0.0, 0.0]),
+                    Vertex::new([0.0, 1.0]),
+                    Vertex::new([1.0, 1.0]),
+
+                    Vertex::new([0.0, 0.0]),
+                    Vertex::new([1.0, 1.0]),
+                    Vertex::new([1.0, 0.0])
+                ],
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+                device.clone(),
+                allocator.clone(),
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT
+            );
+
             let framebuffers = swapchain_image_views.iter().map(|image_view| {
+                let attachments = match &msaa_target {
+                    Some(msaa_target) => vec![msaa_target.view(), *image_view],
+                    None => vec![*image_view]
+                };
+
                 let create_info = vk::FramebufferCreateInfo::builder()
                     .render_pass(render_pass)
-                    .attachments(&[*image_view])
+                    .attachments(&attachments)
                     .width(extent.width)
                     .height(extent.height)
                     .layers(1)
@@ -548,17 +583,25 @@ impl Engine {
                 device.create_fence(&create_info, None).unwrap()
             };
 
-            Engine {
+            let mut engine = Engine {
                 glfw,
                 window,
+                events,
+                window_events: Vec::new(),
                 entry,
                 instance,
                 gpu,
+                gpu_features,
+                gpu_properties,
                 memory_properties,
                 device,
-                device_queue,
+                queue_families,
+                graphics_queue,
+                present_queue,
+                allocator,
                 debug_utils,
                 debug_messenger,
+                validation_state,
                 surface_khr,
                 surface_util,
                 swapchain,
@@ -570,28 +613,90 @@ impl Engine {
                 scissor,
                 swapchain_image_views,
                 swapchain_framebuffers: framebuffers,
+                samples,
+                msaa_target,
                 render_pass,
                 pipeline_layout,
-                graphics_pipeline,
+                pipelines: HashMap::new(),
+                pipeline_cache,
+                shader_watcher,
                 command_pool,
                 descriptor_set_layout,
+                texture_set_layout,
                 draw_command_buffer,
                 image_available_semaphore,
                 render_finished_semaphore,
                 in_flight_fence,
-                image_index: 0
+                image_index: 0,
+                skip_frame: false,
+                bound_pipeline: None,
+                draw_list: Vec::new(),
+                rect_quad,
+                camera: Camera::new([extent.width as f32 / 2.0, extent.height as f32 / 2.0]),
+                gpu_timer,
+                frame_stats: FrameStats::new(),
+                timings: FrameTimings::default(),
+                frame_start: Instant::now(),
+                record_start: Instant::now(),
+                last_report: Instant::now(),
+                show_overlay: false,
+                overlay_key_down: false,
+                clear_color: [0.0, 0.0, 0.0, 1.0],
+                hud_colors: ([1.0, 1.0, 1.0, 1.0], [0.0, 0.0, 0.0, 0.6])
+            };
+
+            engine.name_object(engine.draw_command_buffer, "draw");
+            engine.name_object(engine.rect_quad.handle(), "rect quad");
+            if let Some(msaa_target) = &engine.msaa_target {
+                engine.name_object(msaa_target.image(), "msaa target");
+                engine.name_object(msaa_target.view(), "msaa target view");
+            }
+            if let Some(gpu_timer) = &engine.gpu_timer {
+                engine.name_object(gpu_timer.query_pool(), "frame timestamps");
             }
+            engine.pipeline(PipelineSettings::DEFAULT);
+            debug!("Created graphics pipeline");
+
+            engine
         }
     }
 
     pub fn begin_draw(&mut self) {
+        self.reload_shaders();
+
         unsafe {
+            // waited even on skipped frames so whatever render drops is never still in use
             self.device.wait_for_fences(&[self.in_flight_fence], true, std::u64::MAX).unwrap();
 
-            self.image_index = self.swapchain_util.acquire_next_image(self.swapchain, std::u64::MAX, self.image_available_semaphore, vk::Fence::null()).unwrap().0;
+            // a minimized window has a 0x0 surface, there's nothing to present to until it comes back
+            self.skip_frame = self.window.is_iconified();
+            if self.skip_frame {
+                return;
+            }
+
+            // the last frame is done so its timestamps are ready
+            self.timings.gpu_ms = self.gpu_timer.as_ref().and_then(|t| t.read(&self.device));
+            self.timings.frame_ms = self.frame_start.elapsed().as_secs_f32() * 1000.0;
+            self.frame_start = Instant::now();
+
+            // the window can't be resized so out of date only happens while it's on its way to or from minimized, just skip the frame.
+            // returning before the fence is reset keeps it signalled for next time
+            match self.swapchain_util.acquire_next_image(self.swapchain, std::u64::MAX, self.image_available_semaphore, vk::Fence::null()) {
+                Ok((image_index, _)) => self.image_index = image_index,
+                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                    debug!("Swapchain out of date, skipping the frame");
+                    self.skip_frame = true;
+                    return;
+                }
+                Err(e) => panic!("Failed to acquire a swapchain image: {e}")
+            }
 
             self.device.reset_fences(&[self.in_flight_fence]).unwrap();
+            self.record_start = Instant::now();
             self.device.begin_command_buffer(self.draw_command_buffer, &vk::CommandBufferBeginInfo::builder().build()).unwrap();
+            if let Some(gpu_timer) = &self.gpu_timer {
+                gpu_timer.begin(&self.device, self.draw_command_buffer);
+            }
 
             self.device.cmd_begin_render_pass(
                 self.draw_command_buffer,
@@ -605,7 +710,7 @@ impl Engine {
                     .clear_values(&[
                         vk::ClearValue {
                             color: vk::ClearColorValue {
-                                float32: [0.0, 0.0, 0.0, 1.0],
+                                float32: self.clear_color,
                             }
                         }
                     ])
@@ -613,20 +718,37 @@ impl Engine {
                 vk::SubpassContents::INLINE
             );
 
-            self.device.cmd_bind_pipeline(self.draw_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.graphics_pipeline);
-
             self.device.cmd_set_viewport(self.draw_command_buffer, 0, &[self.viewport]);
             self.device.cmd_set_scissor(self.draw_command_buffer, 0, &[self.scissor]);
         }
+
+        self.bound_pipeline = None;
     }
 
     pub fn end_draw(&mut self) {
+        if self.skip_frame {
+            self.draw_list.clear();
+            return;
+        }
+
+        // counted before the overlay so it shows what the game drew
+        self.timings.draw_calls = self.draw_list.len() as u32;
+        if self.show_overlay {
+            self.draw_overlay();
+        }
+        self.record_draw_list();
+
         unsafe {
             self.device.cmd_end_render_pass(self.draw_command_buffer);
+            if let Some(gpu_timer) = &mut self.gpu_timer {
+                gpu_timer.end(&self.device, self.draw_command_buffer);
+            }
             self.device.end_command_buffer(self.draw_command_buffer).unwrap();
+            self.timings.record_ms = self.record_start.elapsed().as_secs_f32() * 1000.0;
 
+            let submit_start = Instant::now();
             self.device.queue_submit(
-                self.device_queue,
+                self.graphics_queue,
                 &[
                     vk::SubmitInfo::builder()
                         .command_buffers(&[self.draw_command_buffer])
@@ -637,15 +759,297 @@ impl Engine {
                 ],
                 self.in_flight_fence
             ).unwrap();
+            self.timings.submit_ms = submit_start.elapsed().as_secs_f32() * 1000.0;
 
-            self.swapchain_util.queue_present(
-                self.device_queue,
+            let present_start = Instant::now();
+            let presented = self.swapchain_util.queue_present(
+                self.present_queue,
                 &vk::PresentInfoKHR::builder()
                     .wait_semaphores(&[self.render_finished_semaphore])
                     .swapchains(&[self.swapchain])
                     .image_indices(&[self.image_index])
                     .build()
-            ).unwrap();
+            );
+            // suboptimal comes back as Ok(true), both it and out of date sort themselves out once the window is restored
+            match presented {
+                Ok(_) => {}
+                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => debug!("Swapchain out of date, frame wasn't presented"),
+                Err(e) => panic!("Failed to present: {e}")
+            }
+            self.timings.present_ms = present_start.elapsed().as_secs_f32() * 1000.0;
+        }
+
+        if let Some(error) = self.validation_state.take_error() {
+            panic!("Vulkan validation error: {error}");
+        }
+
+        self.frame_stats.push(self.timings);
+
+        if self.last_report.elapsed().as_secs_f32() >= Engine::TIMING_REPORT_INTERVAL {
+            let average = self.frame_stats.average();
+            debug!(
+                fps = self.frame_stats.fps(),
+                frame_ms = average.frame_ms,
+                record_ms = average.record_ms,
+                submit_ms = average.submit_ms,
+                present_ms = average.present_ms,
+                gpu_ms:? = average.gpu_ms,
+                draw_calls = average.draw_calls;
+                "Frame timings"
+            );
+            self.last_report = Instant::now();
+        }
+    }
+
+    /// queues a draw for this frame, it gets recorded in `end_draw` after everything in lower layers
+    pub fn submit(&mut self, layer: Layer, command: DrawCommand) {
+        self.draw_list.push((layer, command));
+    }
+
+    /// covers the whole window, mostly for dimming what's under it
+    pub fn fill_screen(&mut self, layer: Layer, color: [f32; 4]) {
+        self.draw_rect(layer, [0.0, 0.0], [self.extent.width as f32, self.extent.height as f32], color);
+    }
+
+    /// draws a flat rect, `position` and `size` are in pixels from the top left.
+    /// `color` is straight rgba, alpha below 1 blends with whatever is under it
+    pub fn draw_rect(&mut self, layer: Layer, position: [f32; 2], size: [f32; 2], color: [f32; 4]) {
+        self.submit(layer, DrawCommand::Rect { position, size, color });
+    }
+
+    /// draws part of a texture stretched over `size` pixels, `color` tints it and white leaves it as is
+    pub fn draw_sprite(&mut self, layer: Layer, sprite: Sprite, position: [f32; 2], size: [f32; 2], color: [f32; 4]) {
+        self.submit(layer, DrawCommand::Sprite { sprite, position, size, color });
+    }
+
+    /// draws `text` with the built in 3x5 font, `scale` is how many pixels each font pixel takes up
+    pub fn draw_text(&mut self, layer: Layer, text: &str, position: [f32; 2], scale: f32, color: [f32; 4]) {
+        for (x, y, width) in text_runs(text) {
+            self.draw_rect(
+                layer,
+                [position[0] + x as f32 * scale, position[1] + y as f32 * scale],
+                [width as f32 * scale, scale],
+                color
+            );
+        }
+    }
+
+    /// sorts the draw list by layer and records it, each layer gets its own debug label
+    fn record_draw_list(&mut self) {
+        let mut draw_list = std::mem::take(&mut self.draw_list);
+        // stable so draws in the same layer stay in submission order
+        draw_list.sort_by_key(|(layer, _)| *layer);
+
+        // the projection is only pushed when it changes, every pipeline shares the layout so it stays put
+        let (world, screen) = (self.view_projection(), self.projection());
+        let mut pushed_screen_space = None;
+
+        for layer in Layer::ALL {
+            let start = draw_list.partition_point(|(l, _)| *l < layer);
+            let end = draw_list.partition_point(|(l, _)| *l <= layer);
+            if start == end {
+                continue;
+            }
+
+            if pushed_screen_space != Some(layer.screen_space()) {
+                self.push_projection(if layer.screen_space() { &screen } else { &world });
+                pushed_screen_space = Some(layer.screen_space());
+            }
+
+            self.begin_label(layer.label(), layer.label_color());
+            for (_, command) in &draw_list[start..end] {
+                self.record_draw(command);
+            }
+            self.end_label();
+        }
+
+        // hand the allocation back so it isn't reallocated every frame
+        draw_list.clear();
+        self.draw_list = draw_list;
+    }
+
+    fn record_draw(&mut self, command: &DrawCommand) {
+        match *command {
+            DrawCommand::Mesh { pipeline, vertex_buffer, vertex_count, descriptor_set } => {
+                self.bind_pipeline(pipeline);
+
+                unsafe {
+                    if let Some(descriptor_set) = descriptor_set {
+                        self.device.cmd_bind_descriptor_sets(
+                            self.draw_command_buffer,
+                            vk::PipelineBindPoint::GRAPHICS,
+                            self.pipeline_layout,
+                            0,
+                            &[descriptor_set],
+                            &[]
+                        );
+                    }
+                    self.device.cmd_bind_vertex_buffers(self.draw_command_buffer, 0, &[vertex_buffer], &[0]);
+                    self.device.cmd_draw(self.draw_command_buffer, vertex_count, 1, 0, 0);
+                }
+            }
+            DrawCommand::Rect { position, size, color } => {
+                self.bind_pipeline(PipelineSettings::RECT);
+                self.record_quad(position, size, [0.0, 0.0, 1.0, 1.0], color);
+            }
+            DrawCommand::Sprite { sprite, position, size, color } => {
+                self.bind_pipeline(PipelineSettings::SPRITE);
+
+                unsafe {
+                    self.device.cmd_bind_descriptor_sets(
+                        self.draw_command_buffer,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        self.pipeline_layout,
+                        1,
+                        &[sprite.descriptor_set],
+                        &[]
+                    );
+                }
+
+                self.record_quad(position, size, sprite.uv_rect, color);
+            }
+        }
+    }
+
+    fn push_projection(&mut self, projection: &Mat4) {
+        unsafe {
+            self.device.cmd_push_constants(
+                self.draw_command_buffer,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                0,
+                std::slice::from_raw_parts(projection.as_ptr() as *const u8, size_of::<Mat4>())
+            );
+        }
+    }
+
+    /// pushes the rect and draws the unit quad, the pipeline has to be bound already
+    fn record_quad(&mut self, position: [f32; 2], size: [f32; 2], uv_rect: [f32; 4], color: [f32; 4]) {
+        let push = RectPush {
+            rect: [position[0], position[1], size[0], size[1]],
+            uv_rect,
+            color
+        };
+
+        unsafe {
+            self.device.cmd_bind_vertex_buffers(self.draw_command_buffer, 0, &[self.rect_quad.handle()], &[0]);
+            self.device.cmd_push_constants(
+                self.draw_command_buffer,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                PushConstants::RECT_OFFSET,
+                std::slice::from_raw_parts(&push as *const RectPush as *const u8, size_of::<RectPush>())
+            );
+            self.device.cmd_draw(self.draw_command_buffer, self.rect_quad.count(), 1, 0, 0);
+        }
+    }
+
+    fn draw_overlay(&mut self) {
+        let average = self.frame_stats.average();
+        let gpu_ms = match average.gpu_ms {
+            Some(gpu_ms) => format!("{gpu_ms:.2}"),
+            None => "N/A".to_string()
+        };
+        let text = format!(
+            "FPS {:.0}\nFRAME {:.2} MS\nCPU {:.2} MS\nGPU {} MS\nDRAWS {}",
+            self.frame_stats.fps(),
+            average.frame_ms,
+            average.record_ms + average.submit_ms + average.present_ms,
+            gpu_ms,
+            average.draw_calls
+        );
+
+        let scale = 3.0;
+        let (width, height) = text::text_size(&text);
+
+        let (text_color, background_color) = self.hud_colors;
+        self.draw_rect(Layer::Hud, [4.0, 4.0], [(width + 4) as f32 * scale, (height + 4) as f32 * scale], background_color);
+        self.draw_text(Layer::Hud, &text, [4.0 + 2.0 * scale, 4.0 + 2.0 * scale], scale, text_color);
+    }
+
+    /// returns the pipeline for `settings`, creating and caching it the first time
+    pub fn pipeline(&mut self, settings: PipelineSettings) -> vk::Pipeline {
+        let settings = self.supported_settings(settings);
+
+        if let Some(pipeline) = self.pipelines.get(&settings) {
+            return *pipeline;
+        }
+
+        let pipeline = settings.builder().build(self).expect("Failed to create pipeline");
+        self.name_object(pipeline, &settings.to_string());
+        self.shader_watcher.watch(settings.vertex_shader);
+        self.shader_watcher.watch(settings.fragment_shader);
+        self.pipelines.insert(settings, pipeline);
+
+        pipeline
+    }
+
+    /// binds the pipeline for `settings` on the draw command buffer
+    fn bind_pipeline(&mut self, settings: PipelineSettings) {
+        let pipeline = self.pipeline(settings);
+        if self.bound_pipeline == Some(pipeline) {
+            return;
+        }
+
+        unsafe {
+            self.device.cmd_bind_pipeline(self.draw_command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline);
+        }
+        self.bound_pipeline = Some(pipeline);
+    }
+
+    /// swaps out anything the gpu can't do for the closest thing it can
+    fn supported_settings(&self, mut settings: PipelineSettings) -> PipelineSettings {
+        if settings.polygon_mode != vk::PolygonMode::FILL && self.gpu_features.fill_mode_non_solid == 0 {
+            settings.polygon_mode = vk::PolygonMode::FILL;
+        }
+
+        settings
+    }
+
+    /// rebuilds every cached pipeline that uses a shader that changed on disk
+    fn reload_shaders(&mut self) {
+        let changed = self.shader_watcher.poll();
+        if changed.is_empty() {
+            return;
+        }
+
+        unsafe {
+            self.device.device_wait_idle().unwrap();
+        }
+
+        let stale = self.pipelines
+            .keys()
+            .filter(|s| changed.contains(&s.vertex_shader) || changed.contains(&s.fragment_shader))
+            .copied()
+            .collect::<Vec<_>>();
+
+        for settings in stale {
+            // a broken file on disk keeps the last good pipeline rather than going back to the embedded shaders
+            let code = settings.vertex_shader.load_from_disk()
+                .and_then(|vertex| Ok((vertex, settings.fragment_shader.load_from_disk()?)));
+            let (vertex_code, fragment_code) = match code {
+                Ok(code) => code,
+                Err(e) => {
+                    warn!(vertex = settings.vertex_shader.name, fragment = settings.fragment_shader.name; "Failed to load shaders, keeping the old pipeline: {e}");
+                    continue;
+                }
+            };
+
+            // keep drawing with the old pipeline until the new one actually exists
+            let pipeline = match settings.builder().build_with_spirv(self, &vertex_code, &fragment_code) {
+                Ok(pipeline) => pipeline,
+                Err(e) => {
+                    error!(vertex = settings.vertex_shader.name, fragment = settings.fragment_shader.name; "Failed to reload pipeline, keeping the old one: {e}");
+                    continue;
+                }
+            };
+            self.name_object(pipeline, &settings.to_string());
+            let old_pipeline = self.pipelines.insert(settings, pipeline).unwrap();
+
+            unsafe {
+                self.device.destroy_pipeline(old_pipeline, None);
+            }
+            info!(vertex = settings.vertex_shader.name, fragment = settings.fragment_shader.name; "Reloaded pipeline");
         }
     }
 
@@ -658,6 +1062,7 @@ impl Engine {
                     .command_buffer_count(1)
                     .build()
             ).unwrap()[0];
+            self.name_object(command_buffer, "single exec");
 
             self.device.begin_command_buffer(
                 command_buffer,
@@ -675,7 +1080,7 @@ impl Engine {
             self.device.end_command_buffer(command_buffer).unwrap();
 
             self.device.queue_submit(
-                self.device_queue,
+                self.graphics_queue,
                 &[
                     vk::SubmitInfo::builder()
                         .command_buffers(&[command_buffer])
@@ -684,17 +1089,118 @@ impl Engine {
                 vk::Fence::null()
             ).unwrap();
 
-            self.device.queue_wait_idle(self.device_queue).unwrap();
+            self.device.queue_wait_idle(self.graphics_queue).unwrap();
 
             self.device.free_command_buffers(self.command_pool, &[command_buffer]);
         }
     }
 
+    /// gives a vulkan object a name that shows up in validation messages and frame captures.
+    /// does nothing if debug utils isn't available
+    pub fn name_object<T: vk::Handle>(&self, handle: T, name: &str) {
+        if let Some(debug_utils) = &self.debug_utils {
+            set_object_name(debug_utils, &self.device, handle, name);
+        }
+    }
+
+    /// opens a labelled region on the draw command buffer, close it with `end_label`
+    pub fn begin_label(&self, name: &str, color: [f32; 4]) {
+        if let Some(debug_utils) = &self.debug_utils {
+            begin_label(debug_utils, self.draw_command_buffer, name, color);
+        }
+    }
+
+    pub fn end_label(&self) {
+        if let Some(debug_utils) = &self.debug_utils {
+            unsafe {
+                debug_utils.cmd_end_debug_utils_label(self.draw_command_buffer);
+            }
+        }
+    }
+
     pub fn running(&mut self) -> bool {
-        self.glfw.poll_events();
+        // nothing gets drawn while minimized so sleep until something happens instead of spinning
+        if self.window.is_iconified() {
+            self.glfw.wait_events();
+        } else {
+            self.glfw.poll_events();
+        }
+        self.window_events = glfw::flush_messages(&self.events).map(|(_, event)| event).collect();
+
+        // only flip on the press, not every frame its held
+        let overlay_key_down = self.window.get_key(Engine::OVERLAY_KEY) == Action::Press;
+        if overlay_key_down && !self.overlay_key_down {
+            self.show_overlay = !self.show_overlay;
+        }
+        self.overlay_key_down = overlay_key_down;
+
         !self.window.should_close()
     }
 
+    /// linear rgba the frame gets cleared to
+    pub fn set_clear_color(&mut self, color: [f32; 4]) {
+        self.clear_color = color;
+    }
+
+    /// text and background colours of the overlay, straight rgba
+    pub fn set_hud_colors(&mut self, text: [f32; 4], background: [f32; 4]) {
+        self.hud_colors = (text, background);
+    }
+
+    /// window pixels to clip space, what the hud is drawn with. see `projection::ortho` for which way is up
+    pub fn projection(&self) -> Mat4 {
+        ortho(0.0, self.extent.width as f32, 0.0, self.extent.height as f32)
+    }
+
+    /// world pixels to clip space through the camera, what every other layer is drawn with
+    pub fn view_projection(&self) -> Mat4 {
+        self.camera.view_projection(self.viewport_size())
+    }
+
+    /// window size in pixels, lay things out with this rather than `WIDTH`/`HEIGHT` since display scaling can make it bigger.
+    /// the window can't be resized so it never changes
+    pub fn viewport_size(&self) -> [f32; 2] {
+        [self.extent.width as f32, self.extent.height as f32]
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    /// changes show up from the next `end_draw`
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    /// averaged over the last couple seconds of frames
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
+    /// focus, iconify and key events from this frame
+    pub fn window_events(&self) -> &[WindowEvent] {
+        &self.window_events
+    }
+
+    /// true on the frame `key` went down, key repeats don't count
+    pub fn key_pressed(&self, key: Key) -> bool {
+        self.window_events.iter().any(|event| matches!(event, WindowEvent::Key(k, _, Action::Press, _) if *k == key))
+    }
+
+    /// the first connected joystick glfw knows a gamepad mapping for
+    pub fn gamepad_state(&self) -> Option<GamepadState> {
+        (0..16)
+            .filter_map(JoystickId::from_i32)
+            .map(|id| self.glfw.get_joystick(id))
+            .filter(|joystick| joystick.is_gamepad())
+            .find_map(|joystick| joystick.get_gamepad_state())
+    }
+
+    /// the loop ends after this frame
+    pub fn close(&mut self) {
+        self.window.set_should_close(true);
+    }
+
     pub fn window(&self) -> &Window {
         &self.window
     }
@@ -703,10 +1209,18 @@ impl Engine {
         self.device.clone()
     }
 
+    pub fn queue_families(&self) -> QueueFamilies {
+        self.queue_families
+    }
+
     pub fn memory_properties(&self) -> vk::PhysicalDeviceMemoryProperties {
         self.memory_properties
     }
 
+    pub fn allocator(&self) -> SharedAllocator {
+        self.allocator.clone()
+    }
+
     pub fn draw_command_buffer(&self) -> vk::CommandBuffer {
         self.draw_command_buffer
     }
@@ -715,38 +1229,59 @@ impl Engine {
         self.descriptor_set_layout
     }
 
+    pub fn texture_set_layout(&self) -> vk::DescriptorSetLayout {
+        self.texture_set_layout
+    }
+
+    /// the features the device was created with, not everything the gpu supports
+    pub fn gpu_features(&self) -> vk::PhysicalDeviceFeatures {
+        self.gpu_features
+    }
+
+    pub fn max_sampler_anisotropy(&self) -> f32 {
+        self.gpu_properties.limits.max_sampler_anisotropy
+    }
+
     pub fn pipeline_layout(&self) -> vk::PipelineLayout {
         self.pipeline_layout
     }
+
+    pub fn render_pass(&self) -> vk::RenderPass {
+        self.render_pass
+    }
+
+    pub fn pipeline_cache(&self) -> vk::PipelineCache {
+        self.pipeline_cache
+    }
+
+    /// msaa sample count every pipeline has to be built with
+    pub fn samples(&self) -> vk::SampleCountFlags {
+        self.samples
+    }
 }
 
-/// yoinked from ash examples
-unsafe extern "system" fn vulkan_debug_callback(
-    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
-    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
-    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
-    _user_data: *mut std::os::raw::c_void,
-) -> vk::Bool32 {
-    let callback_data = *p_callback_data;
-    let message_id_number = callback_data.message_id_number;
-
-    let message_id_name = if callback_data.p_message_id_name.is_null() {
-        std::borrow::Cow::from("")
-    } else {
-        std::ffi::CStr::from_ptr(callback_data.p_message_id_name).to_string_lossy()
-    };
-
-    let message = if callback_data.p_message.is_null() {
-        std::borrow::Cow::from("")
-    } else {
-        std::ffi::CStr::from_ptr(callback_data.p_message).to_string_lossy()
-    };
-
-    println!(
-        "{message_severity:?}:\n{message_type:?} [{message_id_name} ({message_id_number})] : {message}\n",
-    );
-
-    vk::FALSE
+impl Drop for Engine {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.device_wait_idle().unwrap();
+
+            if let Some(gpu_timer) = &self.gpu_timer {
+                self.device.destroy_query_pool(gpu_timer.query_pool(), None);
+            }
+
+            if let Some(msaa_target) = &self.msaa_target {
+                msaa_target.destroy(&self.device, &self.allocator);
+            }
+
+            save_pipeline_cache(&self.device, self.pipeline_cache);
+            self.device.destroy_pipeline_cache(self.pipeline_cache, None);
+
+            // the messenger points at `validation_state` which is about to be freed
+            if let (Some(debug_utils), Some(debug_messenger)) = (&self.debug_utils, self.debug_messenger) {
+                debug_utils.destroy_debug_utils_messenger(debug_messenger, None);
+            }
+        }
+    }
 }
 
 fn find_memory_type(
@@ -762,10 +1297,3 @@ fn find_memory_type(
 
     None
 }
-
-pub fn px_to_screen(x_px: f32, y_px: f32) -> [f32; 2] {
-    [
-        ((x_px * 2.0) / Engine::WIDTH as f32) - 1.0,
-        (((y_px * 2.0) / Engine::HEIGHT as f32) - 1.0) * -1.0
-    ]
-}
@@ -0,0 +1,98 @@
+use ash::vk;
+use log::warn;
+use super::allocator::{Allocation, SharedAllocator};
+
+/// turns the requested sample count into the highest one the gpu can render colour with that isn't above it
+pub fn pick_sample_count(properties: &vk::PhysicalDeviceProperties, requested: u32) -> vk::SampleCountFlags {
+    let supported = properties.limits.framebuffer_color_sample_counts;
+
+    let sample_count = [64, 32, 16, 8, 4, 2, 1]
+        .into_iter()
+        .filter(|count| *count <= requested.max(1))
+        .map(vk::SampleCountFlags::from_raw)
+        .find(|flags| supported.contains(*flags))
+        .unwrap_or(vk::SampleCountFlags::TYPE_1);
+
+    if sample_count.as_raw() != requested.max(1) {
+        warn!(requested = requested, using = sample_count.as_raw(); "Gpu can't do that many msaa samples");
+    }
+
+    sample_count
+}
+
+/// the multisampled image everything gets drawn into before it's resolved onto the swapchain image
+pub struct MsaaTarget {
+    image: vk::Image,
+    view: vk::ImageView,
+    allocation: Allocation
+}
+
+impl MsaaTarget {
+    pub fn new(
+        device: &ash::Device,
+        allocator: &SharedAllocator,
+        format: vk::Format,
+        extent: vk::Extent2D,
+        samples: vk::SampleCountFlags
+    ) -> MsaaTarget {
+        unsafe {
+            let create_info = vk::ImageCreateInfo::builder()
+                .image_type(vk::ImageType::TYPE_2D)
+                .format(format)
+                .extent(vk::Extent3D {
+                    width: extent.width,
+                    height: extent.height,
+                    depth: 1
+                })
+                .mip_levels(1)
+                .array_layers(1)
+                .samples(samples)
+                .tiling(vk::ImageTiling::OPTIMAL)
+                // never read back, only resolved, so tilers can keep it on chip
+                .usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT)
+                .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                .initial_layout(vk::ImageLayout::UNDEFINED)
+                .build();
+
+            let image = device.create_image(&create_info, None).unwrap();
+            let allocation = allocator.borrow_mut().allocate_image(image, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+
+            let view_create_info = vk::ImageViewCreateInfo::builder()
+                .image(image)
+                .view_type(vk::ImageViewType::TYPE_2D)
+                .format(format)
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1
+                })
+                .build();
+
+            let view = device.create_image_view(&view_create_info, None).unwrap();
+
+            MsaaTarget {
+                image,
+                view,
+                allocation
+            }
+        }
+    }
+
+    pub fn image(&self) -> vk::Image {
+        self.image
+    }
+
+    pub fn view(&self) -> vk::ImageView {
+        self.view
+    }
+
+    pub fn destroy(&self, device: &ash::Device, allocator: &SharedAllocator) {
+        unsafe {
+            device.destroy_image_view(self.view, None);
+            device.destroy_image(self.image, None);
+        }
+        allocator.borrow_mut().free(&self.allocation);
+    }
+}
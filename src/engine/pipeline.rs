@@ -0,0 +1,273 @@
+use std::{ffi::CString, fmt};
+use ash::vk;
+use super::{shader::{create_shader_module, ShaderSource}, vertex::Vertex, Engine};
+
+/// everything that makes one pipeline different from another, also used as the cache key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineSettings {
+    pub vertex_shader: ShaderSource,
+    pub fragment_shader: ShaderSource,
+    pub topology: vk::PrimitiveTopology,
+    pub polygon_mode: vk::PolygonMode,
+    pub cull_mode: vk::CullModeFlags,
+    pub blend: BlendMode
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    Opaque,
+    /// straight alpha, `src * a + dst * (1 - a)`
+    Alpha,
+    /// the shader already multiplied rgb by alpha, `src + dst * (1 - a)`.
+    /// fades to transparent without the dark fringe straight alpha gets
+    Premultiplied
+}
+
+impl PipelineSettings {
+    pub const DEFAULT: PipelineSettings = PipelineSettings {
+        vertex_shader: ShaderSource::DEFAULT_VERT,
+        fragment_shader: ShaderSource::DEFAULT_FRAG,
+        topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+        polygon_mode: vk::PolygonMode::FILL,
+        cull_mode: vk::CullModeFlags::BACK,
+        blend: BlendMode::Opaque
+    };
+
+    pub const ALPHA_BLENDED: PipelineSettings = PipelineSettings {
+        blend: BlendMode::Alpha,
+        ..PipelineSettings::DEFAULT
+    };
+
+    /// what everything in the game draws with, the built in shaders output premultiplied colour
+    pub const PREMULTIPLIED: PipelineSettings = PipelineSettings {
+        blend: BlendMode::Premultiplied,
+        ..PipelineSettings::DEFAULT
+    };
+
+    /// flat coloured rects in pixels, used by `Engine::draw_rect` and the timing overlay.
+    /// not culled since the quad isn't flipped like the tiles are
+    pub const RECT: PipelineSettings = PipelineSettings {
+        vertex_shader: ShaderSource::RECT_VERT,
+        fragment_shader: ShaderSource::RECT_FRAG,
+        cull_mode: vk::CullModeFlags::NONE,
+        blend: BlendMode::Premultiplied,
+        ..PipelineSettings::DEFAULT
+    };
+
+    /// textured rects, used by `Engine::draw_sprite`
+    pub const SPRITE: PipelineSettings = PipelineSettings {
+        vertex_shader: ShaderSource::SPRITE_VERT,
+        fragment_shader: ShaderSource::SPRITE_FRAG,
+        ..PipelineSettings::RECT
+    };
+
+    pub const LINE_LIST: PipelineSettings = PipelineSettings {
+        topology: vk::PrimitiveTopology::LINE_LIST,
+        cull_mode: vk::CullModeFlags::NONE,
+        ..PipelineSettings::DEFAULT
+    };
+
+    /// needs `fill_mode_non_solid`, the engine falls back to `FILL` without it
+    pub const WIREFRAME: PipelineSettings = PipelineSettings {
+        polygon_mode: vk::PolygonMode::LINE,
+        cull_mode: vk::CullModeFlags::NONE,
+        ..PipelineSettings::DEFAULT
+    };
+
+    pub fn builder(self) -> PipelineBuilder {
+        PipelineBuilder {
+            settings: self
+        }
+    }
+}
+
+impl fmt::Display for PipelineSettings {
+    /// used as the pipeline's debug name
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} / {} {:?} {:?} {:?}",
+            self.vertex_shader.name,
+            self.fragment_shader.name,
+            self.topology,
+            self.polygon_mode,
+            self.blend
+        )
+    }
+}
+
+pub struct PipelineBuilder {
+    settings: PipelineSettings
+}
+
+impl PipelineBuilder {
+    pub fn shaders(mut self, vertex_shader: ShaderSource, fragment_shader: ShaderSource) -> PipelineBuilder {
+        self.settings.vertex_shader = vertex_shader;
+        self.settings.fragment_shader = fragment_shader;
+        self
+    }
+
+    pub fn topology(mut self, topology: vk::PrimitiveTopology) -> PipelineBuilder {
+        self.settings.topology = topology;
+        self
+    }
+
+    pub fn polygon_mode(mut self, polygon_mode: vk::PolygonMode) -> PipelineBuilder {
+        self.settings.polygon_mode = polygon_mode;
+        self
+    }
+
+    pub fn cull_mode(mut self, cull_mode: vk::CullModeFlags) -> PipelineBuilder {
+        self.settings.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn blend(mut self, blend: BlendMode) -> PipelineBuilder {
+        self.settings.blend = blend;
+        self
+    }
+
+    pub fn settings(&self) -> PipelineSettings {
+        self.settings
+    }
+
+    /// creates the pipeline against the engine's render pass and layout, using the embedded shaders if the ones on disk are broken.
+    /// use `Engine::pipeline` instead if you want it cached
+    pub fn build(&self, engine: &Engine) -> Result<vk::Pipeline, vk::Result> {
+        self.build_with_spirv(engine, &self.settings.vertex_shader.load(), &self.settings.fragment_shader.load())
+    }
+
+    /// like `build` but with the spir-v already loaded.
+    /// fails rather than panicking so hot reloading can keep the old pipeline
+    pub fn build_with_spirv(&self, engine: &Engine, vertex_code: &[u32], fragment_code: &[u32]) -> Result<vk::Pipeline, vk::Result> {
+        let device = engine.device();
+        let settings = self.settings;
+
+        unsafe {
+            let vertex_shader_module = create_shader_module(&device, vertex_code)?;
+            let fragment_shader_module = match create_shader_module(&device, fragment_code) {
+                Ok(module) => module,
+                Err(e) => {
+                    device.destroy_shader_module(vertex_shader_module, None);
+                    return Err(e);
+                }
+            };
+
+            let entry_point_name = CString::new("main").unwrap();
+            let shader_stages = [
+                vk::PipelineShaderStageCreateInfo::builder()
+                    .stage(vk::ShaderStageFlags::VERTEX)
+                    .module(vertex_shader_module)
+                    .name(&entry_point_name)
+                    .build(),
+                vk::PipelineShaderStageCreateInfo::builder()
+                    .stage(vk::ShaderStageFlags::FRAGMENT)
+                    .module(fragment_shader_module)
+                    .name(&entry_point_name)
+                    .build()
+            ];
+
+            let dynamic_states = [
+                vk::DynamicState::VIEWPORT,
+                vk::DynamicState::SCISSOR
+            ];
+
+            let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
+                .dynamic_states(&dynamic_states)
+                .build();
+
+            let binding_descriptions = [Vertex::get_binding_description()];
+            let attribute_descriptions = Vertex::get_attribute_descriptions();
+            let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::builder()
+                .vertex_binding_descriptions(&binding_descriptions)
+                .vertex_attribute_descriptions(&attribute_descriptions)
+                .build();
+
+            let input_assembly_info = vk::PipelineInputAssemblyStateCreateInfo::builder()
+                .topology(settings.topology)
+                .primitive_restart_enable(false)
+                .build();
+
+            let viewport_state_info = vk::PipelineViewportStateCreateInfo::builder()
+                .viewport_count(1)
+                .scissor_count(1)
+                .build();
+
+            let rasterizer_info = vk::PipelineRasterizationStateCreateInfo::builder()
+                .depth_clamp_enable(false)
+                .rasterizer_discard_enable(false)
+                .polygon_mode(settings.polygon_mode)
+                .line_width(1.0)
+                .cull_mode(settings.cull_mode)
+                .front_face(vk::FrontFace::CLOCKWISE)
+                .depth_bias_enable(false)
+                .depth_bias_constant_factor(0.0)
+                .depth_bias_clamp(0.0)
+                .depth_bias_slope_factor(0.0)
+                .build();
+
+            let multisample_info = vk::PipelineMultisampleStateCreateInfo::builder()
+                .sample_shading_enable(false)
+                .rasterization_samples(engine.samples())
+                .build();
+
+            let color_blend_attachments = [
+                match settings.blend {
+                    BlendMode::Opaque => vk::PipelineColorBlendAttachmentState::builder()
+                        .color_write_mask(vk::ColorComponentFlags::RGBA)
+                        .blend_enable(false)
+                        .build(),
+                    BlendMode::Alpha => vk::PipelineColorBlendAttachmentState::builder()
+                        .color_write_mask(vk::ColorComponentFlags::RGBA)
+                        .blend_enable(true)
+                        .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+                        .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                        .color_blend_op(vk::BlendOp::ADD)
+                        .src_alpha_blend_factor(vk::BlendFactor::ONE)
+                        .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                        .alpha_blend_op(vk::BlendOp::ADD)
+                        .build(),
+                    BlendMode::Premultiplied => vk::PipelineColorBlendAttachmentState::builder()
+                        .color_write_mask(vk::ColorComponentFlags::RGBA)
+                        .blend_enable(true)
+                        .src_color_blend_factor(vk::BlendFactor::ONE)
+                        .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                        .color_blend_op(vk::BlendOp::ADD)
+                        .src_alpha_blend_factor(vk::BlendFactor::ONE)
+                        .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                        .alpha_blend_op(vk::BlendOp::ADD)
+                        .build()
+                }
+            ];
+
+            let color_blend_info = vk::PipelineColorBlendStateCreateInfo::builder()
+                .logic_op_enable(false)
+                .attachments(&color_blend_attachments)
+                .build();
+
+            let create_info = vk::GraphicsPipelineCreateInfo::builder()
+                .stages(&shader_stages)
+                .dynamic_state(&dynamic_state)
+                .vertex_input_state(&vertex_input_info)
+                .input_assembly_state(&input_assembly_info)
+                .viewport_state(&viewport_state_info)
+                .rasterization_state(&rasterizer_info)
+                .multisample_state(&multisample_info)
+                .color_blend_state(&color_blend_info)
+                .layout(engine.pipeline_layout())
+                .render_pass(engine.render_pass())
+                .subpass(0)
+                .build();
+
+            let graphics_pipeline = device.create_graphics_pipelines(engine.pipeline_cache(), &[create_info], None)
+                .map(|pipelines| pipelines[0])
+                .map_err(|(_, e)| e);
+
+            // the pipeline keeps what it needs from the modules, and they aren't needed at all if it failed
+            device.destroy_shader_module(vertex_shader_module, None);
+            device.destroy_shader_module(fragment_shader_module, None);
+
+            graphics_pipeline
+        }
+    }
+}
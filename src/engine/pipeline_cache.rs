@@ -0,0 +1,133 @@
+use std::{fs, path::PathBuf};
+use ash::vk;
+use log::{debug, warn};
+use super::paths::cache_dir;
+
+/// size of `VkPipelineCacheHeaderVersionOne`
+const HEADER_SIZE: usize = 32;
+
+fn cache_path() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("pipeline_cache.bin"))
+}
+
+/// creates the pipeline cache, seeded from disk if whats there was made by this exact gpu and driver
+pub fn load_pipeline_cache(device: &ash::Device, properties: &vk::PhysicalDeviceProperties) -> vk::PipelineCache {
+    let data = cache_path()
+        .and_then(|path| fs::read(path).ok())
+        .filter(|data| {
+            let valid = header_matches(data, properties);
+            if !valid {
+                warn!("Pipeline cache on disk is from a different gpu or driver, starting fresh");
+            }
+            valid
+        })
+        .unwrap_or_default();
+    debug!(bytes = data.len(); "Seeding pipeline cache");
+
+    unsafe {
+        let create_info = vk::PipelineCacheCreateInfo::builder()
+            .initial_data(&data)
+            .build();
+
+        device.create_pipeline_cache(&create_info, None).unwrap()
+    }
+}
+
+/// writes the cache back to disk, failing to save just means a slower start next time
+pub fn save_pipeline_cache(device: &ash::Device, pipeline_cache: vk::PipelineCache) {
+    let Some(path) = cache_path() else { return };
+
+    let data = unsafe { device.get_pipeline_cache_data(pipeline_cache).unwrap() };
+
+    let result = fs::create_dir_all(path.parent().unwrap()).and_then(|_| fs::write(&path, data));
+    if let Err(e) = result {
+        warn!("Failed to save pipeline cache to {}: {e}", path.display());
+    }
+}
+
+fn header_matches(data: &[u8], properties: &vk::PhysicalDeviceProperties) -> bool {
+    if data.len() < HEADER_SIZE {
+        return false;
+    }
+
+    let read_u32 = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+
+    let header_length = read_u32(0) as usize;
+    let header_version = read_u32(4);
+    let vendor_id = read_u32(8);
+    let device_id = read_u32(12);
+    let uuid = &data[16..32];
+
+    header_length >= HEADER_SIZE &&
+    header_version == vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32 &&
+    vendor_id == properties.vendor_id &&
+    device_id == properties.device_id &&
+    uuid == properties.pipeline_cache_uuid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn properties() -> vk::PhysicalDeviceProperties {
+        vk::PhysicalDeviceProperties {
+            vendor_id: 0x10de,
+            device_id: 0x2484,
+            pipeline_cache_uuid: [7; vk::UUID_SIZE],
+            ..Default::default()
+        }
+    }
+
+    /// what a driver writes for `properties`, followed by some cache data
+    fn cache_data(properties: &vk::PhysicalDeviceProperties) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        data.extend_from_slice(&(vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32).to_le_bytes());
+        data.extend_from_slice(&properties.vendor_id.to_le_bytes());
+        data.extend_from_slice(&properties.device_id.to_le_bytes());
+        data.extend_from_slice(&properties.pipeline_cache_uuid);
+        data.extend_from_slice(&[0xab; 64]);
+        data
+    }
+
+    #[test]
+    fn matching_header() {
+        assert!(header_matches(&cache_data(&properties()), &properties()));
+    }
+
+    #[test]
+    fn different_gpu_or_driver() {
+        let data = cache_data(&properties());
+
+        let vendor = vk::PhysicalDeviceProperties { vendor_id: 0x1002, ..properties() };
+        let device = vk::PhysicalDeviceProperties { device_id: 0x2485, ..properties() };
+        let mut uuid = properties();
+        uuid.pipeline_cache_uuid[15] = 8;
+
+        for other in [vendor, device, uuid] {
+            assert!(!header_matches(&data, &other));
+        }
+    }
+
+    #[test]
+    fn bad_header_fields() {
+        let mut short_length = cache_data(&properties());
+        short_length[0] = 16;
+        let mut version = cache_data(&properties());
+        version[4] = 2;
+
+        for data in [short_length, version] {
+            assert!(!header_matches(&data, &properties()));
+        }
+    }
+
+    #[test]
+    fn truncated_data() {
+        let data = cache_data(&properties());
+
+        for len in [0, 4, 16, HEADER_SIZE - 1] {
+            assert!(!header_matches(&data[..len], &properties()));
+        }
+        assert!(header_matches(&data[..HEADER_SIZE], &properties()));
+    }
+}
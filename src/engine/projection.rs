@@ -0,0 +1,16 @@
+/// column major like glsl's mat4, `m[column][row]`
+pub type Mat4 = [[f32; 4]; 4];
+
+/// maps pixels to vulkan clip space.
+///
+/// everything drawn through the engine uses the same pixel space: 0,0 is the top left of the window,
+/// x goes right and y goes down, same as vulkan's framebuffer and glfw's cursor position.
+/// nothing flips y anywhere so "up" on the screen is always a smaller y
+pub fn ortho(left: f32, right: f32, top: f32, bottom: f32) -> Mat4 {
+    [
+        [2.0 / (right - left), 0.0, 0.0, 0.0],
+        [0.0, 2.0 / (bottom - top), 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [-(right + left) / (right - left), -(bottom + top) / (bottom - top), 0.0, 1.0]
+    ]
+}
@@ -0,0 +1,245 @@
+use std::{fmt, fs, io, path::{Path, PathBuf}, process::{Child, Command, Stdio}, time::{Instant, SystemTime}};
+use ash::vk;
+use log::{error, info, warn};
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+/// a shader that can be loaded from `shaders/` at runtime, with the copy baked into the binary as a fallback
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShaderSource {
+    /// glsl file name inside `shaders/`, the spir-v lives next to it with `.spv` on the end
+    pub name: &'static str,
+    pub embedded: &'static [u8]
+}
+
+impl ShaderSource {
+    pub const DEFAULT_VERT: ShaderSource = ShaderSource {
+        name: "default.vert",
+        embedded: include_bytes!("../../shaders/default.vert.spv")
+    };
+    pub const DEFAULT_FRAG: ShaderSource = ShaderSource {
+        name: "default.frag",
+        embedded: include_bytes!("../../shaders/default.frag.spv")
+    };
+    /// positions a unit quad with push constants, see `RectPush`
+    pub const RECT_VERT: ShaderSource = ShaderSource {
+        name: "rect.vert",
+        embedded: include_bytes!("../../shaders/rect.vert.spv")
+    };
+    pub const RECT_FRAG: ShaderSource = ShaderSource {
+        name: "rect.frag",
+        embedded: include_bytes!("../../shaders/rect.frag.spv")
+    };
+    /// like the rect shaders but samples a texture from set 1
+    pub const SPRITE_VERT: ShaderSource = ShaderSource {
+        name: "sprite.vert",
+        embedded: include_bytes!("../../shaders/sprite.vert.spv")
+    };
+    pub const SPRITE_FRAG: ShaderSource = ShaderSource {
+        name: "sprite.frag",
+        embedded: include_bytes!("../../shaders/sprite.frag.spv")
+    };
+
+    pub fn glsl_path(&self) -> PathBuf {
+        Path::new(SHADER_DIR).join(self.name)
+    }
+
+    pub fn spirv_path(&self) -> PathBuf {
+        Path::new(SHADER_DIR).join(format!("{}.spv", self.name))
+    }
+
+    /// reads the spir-v from disk, falling back to the embedded copy if its missing or broken.
+    /// only for startup, a reload should keep what it had with `load_from_disk` instead
+    pub fn load(&self) -> Vec<u32> {
+        match self.load_from_disk() {
+            Ok(code) => code,
+            Err(e) => {
+                warn!("Failed to load {}: {e}, using the embedded copy", self.spirv_path().display());
+                parse_spirv(self.embedded).expect("Embedded shader is not valid spir-v")
+            }
+        }
+    }
+
+    /// reads the spir-v from disk with no fallback
+    pub fn load_from_disk(&self) -> Result<Vec<u32>, ShaderError> {
+        fs::read(self.spirv_path()).map_err(ShaderError::Io).and_then(|bytes| parse_spirv(&bytes))
+    }
+}
+
+pub const SHADER_DIR: &str = "shaders";
+
+#[derive(Debug)]
+pub enum ShaderError {
+    Io(io::Error),
+    /// spir-v is made of 32 bit words so the length has to be a multiple of 4
+    Unaligned(usize),
+    BadMagic(u32)
+}
+
+/// checks the magic number and turns the bytes into words.
+/// copying into a `Vec<u32>` also sorts out alignment since `include_bytes!` only guarantees 1
+pub fn parse_spirv(bytes: &[u8]) -> Result<Vec<u32>, ShaderError> {
+    if !bytes.len().is_multiple_of(4) || bytes.is_empty() {
+        return Err(ShaderError::Unaligned(bytes.len()));
+    }
+
+    let mut code = bytes
+        .chunks_exact(4)
+        .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+        .collect::<Vec<_>>();
+
+    if code[0] == SPIRV_MAGIC.swap_bytes() {
+        code.iter_mut().for_each(|w| *w = w.swap_bytes());
+    }
+    if code[0] != SPIRV_MAGIC {
+        return Err(ShaderError::BadMagic(code[0]));
+    }
+
+    Ok(code)
+}
+
+/// fails instead of panicking so a bad shader from a hot reload can't take the game down
+pub fn create_shader_module(device: &ash::Device, code: &[u32]) -> Result<vk::ShaderModule, vk::Result> {
+    unsafe {
+        let create_info = vk::ShaderModuleCreateInfo::builder()
+            .code(code)
+            .build();
+
+        device.create_shader_module(&create_info, None)
+    }
+}
+
+/// polls the shader files for changes so pipelines can be rebuilt while the game runs
+pub struct ShaderWatcher {
+    shaders: Vec<WatchedShader>,
+    last_poll: Instant
+}
+
+struct WatchedShader {
+    source: ShaderSource,
+    glsl_modified: Option<SystemTime>,
+    spirv_modified: Option<SystemTime>,
+    /// the glsl changed but might still be being saved, it gets compiled once a poll goes by without it changing again
+    glsl_pending: bool,
+    /// `glslc` runs in the background so a compile never holds up a frame
+    compiling: Option<Child>
+}
+
+impl ShaderWatcher {
+    /// stat'ing files every frame is a waste when the game runs uncapped.
+    /// also how long a glsl file has to sit still before it gets compiled
+    const POLL_INTERVAL: f32 = 0.5;
+
+    pub fn new() -> ShaderWatcher {
+        ShaderWatcher {
+            shaders: Vec::new(),
+            last_poll: Instant::now()
+        }
+    }
+
+    pub fn watch(&mut self, source: ShaderSource) {
+        if self.shaders.iter().any(|s| s.source == source) {
+            return;
+        }
+
+        self.shaders.push(WatchedShader {
+            source,
+            glsl_modified: modified(&source.glsl_path()),
+            spirv_modified: modified(&source.spirv_path()),
+            glsl_pending: false,
+            compiling: None
+        });
+    }
+
+    /// returns the shaders whose spir-v changed since the last poll.
+    /// if a glsl file was edited it gets recompiled with `glslc` first, the spir-v shows up here on a later poll once that's done
+    pub fn poll(&mut self) -> Vec<ShaderSource> {
+        if self.last_poll.elapsed().as_secs_f32() < ShaderWatcher::POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+
+        let mut changed = Vec::new();
+
+        for shader in self.shaders.iter_mut() {
+            let glsl_modified = modified(&shader.source.glsl_path());
+            if glsl_modified.is_some() && glsl_modified != shader.glsl_modified {
+                // still being written, wait for it to settle
+                shader.glsl_modified = glsl_modified;
+                shader.glsl_pending = true;
+            } else if shader.glsl_pending && shader.compiling.is_none() {
+                shader.glsl_pending = false;
+                shader.compiling = spawn_glslc(&shader.source);
+            }
+
+            if let Some(child) = &mut shader.compiling {
+                match child.try_wait() {
+                    // don't look at the spir-v while glslc might be half way through writing it
+                    Ok(None) => continue,
+                    Ok(Some(_)) => log_glslc(&shader.source, shader.compiling.take().unwrap()),
+                    Err(e) => {
+                        warn!("Lost track of glslc compiling {}: {e}", shader.source.name);
+                        shader.compiling = None;
+                    }
+                }
+            }
+
+            let spirv_modified = modified(&shader.source.spirv_path());
+            if spirv_modified.is_some() && spirv_modified != shader.spirv_modified {
+                shader.spirv_modified = spirv_modified;
+                changed.push(shader.source);
+            }
+        }
+
+        changed
+    }
+}
+
+impl Default for ShaderWatcher {
+    fn default() -> Self {
+        ShaderWatcher::new()
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn spawn_glslc(source: &ShaderSource) -> Option<Child> {
+    let child = Command::new("glslc")
+        .arg(source.glsl_path())
+        .arg("-o")
+        .arg(source.spirv_path())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    match child {
+        Ok(child) => Some(child),
+        Err(e) => {
+            warn!("Couldn't run glslc to compile {}: {e}", source.name);
+            None
+        }
+    }
+}
+
+/// `child` has already exited so this doesn't block
+fn log_glslc(source: &ShaderSource, child: Child) {
+    match child.wait_with_output() {
+        Ok(output) if output.status.success() => info!(shader = source.name; "Recompiled shader"),
+        Ok(output) => error!("Failed to compile {}:\n{}", source.name, String::from_utf8_lossy(&output.stderr)),
+        Err(e) => warn!("Lost track of glslc compiling {}: {e}", source.name)
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Io(e) => write!(f, "{e}"),
+            ShaderError::Unaligned(len) => write!(f, "spir-v length {len} is not a multiple of 4"),
+            ShaderError::BadMagic(magic) => write!(f, "bad spir-v magic number {magic:#010x}")
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
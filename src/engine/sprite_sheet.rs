@@ -0,0 +1,217 @@
+use std::{collections::HashMap, fmt, fs, io, path::{Path, PathBuf}};
+use serde::Deserialize;
+use super::{image::{Image, ImageError}, texture::{Sprite, Texture}, Engine};
+
+/// a texture cut up into named sprites and animations, described by a toml file next to the image:
+///
+/// ```toml
+/// image = "snake.png"
+///
+/// [regions]
+/// head = { x = 0, y = 0, width = 16, height = 16 }
+/// apple_0 = { x = 16, y = 0, width = 16, height = 16 }
+/// apple_1 = { x = 32, y = 0, width = 16, height = 16 }
+///
+/// [animations]
+/// apple = { frames = ["apple_0", "apple_1"], fps = 4.0 }
+/// ```
+pub struct SpriteSheet {
+    texture: Texture,
+    regions: HashMap<String, Sprite>,
+    animations: HashMap<String, Animation>
+}
+
+#[derive(Debug, Clone)]
+pub struct Animation {
+    pub frames: Vec<Sprite>,
+    pub fps: f32,
+    /// stops on the last frame otherwise
+    pub looping: bool
+}
+
+#[derive(Debug)]
+pub enum SheetError {
+    Io(PathBuf, io::Error),
+    Toml(toml::de::Error),
+    Image(PathBuf, ImageError),
+    OutOfBounds(String),
+    MissingRegion {
+        animation: String,
+        region: String
+    }
+}
+
+#[derive(Deserialize)]
+struct SheetFile {
+    /// relative to the toml file
+    image: PathBuf,
+    #[serde(default)]
+    regions: HashMap<String, RegionFile>,
+    #[serde(default)]
+    animations: HashMap<String, AnimationFile>
+}
+
+#[derive(Deserialize)]
+struct RegionFile {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32
+}
+
+#[derive(Deserialize)]
+struct AnimationFile {
+    frames: Vec<String>,
+    fps: f32,
+    #[serde(default = "default_looping")]
+    looping: bool
+}
+
+fn default_looping() -> bool {
+    true
+}
+
+impl SpriteSheet {
+    pub fn load(engine: &Engine, path: &Path) -> Result<SpriteSheet, SheetError> {
+        let text = fs::read_to_string(path).map_err(|e| SheetError::Io(path.to_path_buf(), e))?;
+        let sheet_file: SheetFile = toml::from_str(&text).map_err(SheetError::Toml)?;
+
+        let image_path = path.parent().unwrap_or(Path::new("")).join(&sheet_file.image);
+        let image = Image::load(&image_path).map_err(|e| SheetError::Image(image_path.clone(), e))?;
+
+        check_regions(&sheet_file.regions, image.width, image.height)?;
+
+        let texture = Texture::from_rgba(engine, image.width, image.height, &image.pixels, &image_path.display().to_string());
+
+        let regions = sheet_file.regions
+            .iter()
+            .map(|(name, r)| (name.clone(), texture.sub_sprite(r.x, r.y, r.width, r.height)))
+            .collect::<HashMap<_, _>>();
+
+        let mut animations = HashMap::new();
+        for (name, animation) in sheet_file.animations {
+            let frames = animation.frames
+                .iter()
+                .map(|frame| regions.get(frame).copied().ok_or_else(|| SheetError::MissingRegion {
+                    animation: name.clone(),
+                    region: frame.clone()
+                }))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            animations.insert(name, Animation {
+                frames,
+                fps: animation.fps,
+                looping: animation.looping
+            });
+        }
+
+        Ok(SpriteSheet {
+            texture,
+            regions,
+            animations
+        })
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn sprite(&self, name: &str) -> Option<Sprite> {
+        self.regions.get(name).copied()
+    }
+
+    pub fn animation(&self, name: &str) -> Option<&Animation> {
+        self.animations.get(name)
+    }
+
+    /// the animation's frame `seconds` in, or the plain region if there's no animation called `name`
+    pub fn frame(&self, name: &str, seconds: f32) -> Option<Sprite> {
+        match self.animations.get(name) {
+            Some(animation) => animation.frame(seconds),
+            None => self.sprite(name)
+        }
+    }
+}
+
+/// every region has to fit inside the image
+fn check_regions(regions: &HashMap<String, RegionFile>, width: u32, height: u32) -> Result<(), SheetError> {
+    for (name, region) in regions {
+        // checked so a huge x or y can't wrap around into bounds
+        let fits = |start: u32, size: u32, limit: u32| start.checked_add(size).is_some_and(|end| end <= limit);
+        if !fits(region.x, region.width, width) || !fits(region.y, region.height, height) {
+            return Err(SheetError::OutOfBounds(name.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+impl Animation {
+    pub fn frame(&self, seconds: f32) -> Option<Sprite> {
+        if self.frames.is_empty() {
+            return None;
+        }
+
+        let frame = (seconds.max(0.0) * self.fps) as usize;
+        let frame = if self.looping {
+            frame % self.frames.len()
+        } else {
+            frame.min(self.frames.len() - 1)
+        };
+
+        Some(self.frames[frame])
+    }
+}
+
+impl fmt::Display for SheetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SheetError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            SheetError::Toml(e) => write!(f, "{e}"),
+            SheetError::Image(path, e) => write!(f, "{}: {e}", path.display()),
+            SheetError::OutOfBounds(region) => write!(f, "region {region} goes past the edge of the image"),
+            SheetError::MissingRegion { animation, region } => write!(f, "animation {animation} uses region {region} which doesn't exist")
+        }
+    }
+}
+
+impl std::error::Error for SheetError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_example_sheet() {
+        let sheet_file: SheetFile = toml::from_str(include_str!("../../assets/sprites/snake.toml")).unwrap();
+
+        assert_eq!(sheet_file.image, PathBuf::from("snake.png"));
+        let apple = &sheet_file.regions["apple_1"];
+        assert_eq!((apple.x, apple.y, apple.width, apple.height), (64, 0, 16, 16));
+        assert_eq!(sheet_file.animations["apple"].frames, ["apple_0", "apple_1"]);
+        assert!(sheet_file.animations["apple"].looping);
+        assert!(check_regions(&sheet_file.regions, 96, 16).is_ok());
+    }
+
+    #[test]
+    fn region_past_the_edge() {
+        let sheet_file: SheetFile = toml::from_str(r#"
+            image = "sheet.png"
+            [regions]
+            wide = { x = 70, y = 0, width = 16, height = 16 }
+        "#).unwrap();
+
+        assert!(matches!(check_regions(&sheet_file.regions, 80, 16), Err(SheetError::OutOfBounds(name)) if name == "wide"));
+    }
+
+    #[test]
+    fn region_overflowing_u32() {
+        let sheet_file: SheetFile = toml::from_str(r#"
+            image = "sheet.png"
+            [regions]
+            wrapped = { x = 0, y = 4294967295, width = 16, height = 2 }
+        "#).unwrap();
+
+        assert!(matches!(check_regions(&sheet_file.regions, 80, 16), Err(SheetError::OutOfBounds(_))));
+    }
+}
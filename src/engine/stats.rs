@@ -0,0 +1,133 @@
+use std::collections::VecDeque;
+use ash::vk;
+
+/// how long one frame took, all in milliseconds
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameTimings {
+    /// begin_draw to begin_draw
+    pub frame_ms: f32,
+    /// cpu time spent recording commands
+    pub record_ms: f32,
+    pub submit_ms: f32,
+    pub present_ms: f32,
+    /// render pass time measured with timestamp queries, `None` if the gpu can't do them
+    pub gpu_ms: Option<f32>,
+    pub draw_calls: u32
+}
+
+/// the last few seconds worth of frames, averaged so the overlay doesn't flicker
+pub struct FrameStats {
+    samples: VecDeque<FrameTimings>
+}
+
+impl FrameStats {
+    const SAMPLE_COUNT: usize = 120;
+
+    pub fn new() -> FrameStats {
+        FrameStats {
+            samples: VecDeque::with_capacity(FrameStats::SAMPLE_COUNT)
+        }
+    }
+
+    pub fn push(&mut self, timings: FrameTimings) {
+        if self.samples.len() == FrameStats::SAMPLE_COUNT {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(timings);
+    }
+
+    pub fn average(&self) -> FrameTimings {
+        let count = self.samples.len().max(1) as f32;
+        let gpu_samples = self.samples.iter().filter_map(|s| s.gpu_ms).collect::<Vec<_>>();
+
+        FrameTimings {
+            frame_ms: self.samples.iter().map(|s| s.frame_ms).sum::<f32>() / count,
+            record_ms: self.samples.iter().map(|s| s.record_ms).sum::<f32>() / count,
+            submit_ms: self.samples.iter().map(|s| s.submit_ms).sum::<f32>() / count,
+            present_ms: self.samples.iter().map(|s| s.present_ms).sum::<f32>() / count,
+            gpu_ms: (!gpu_samples.is_empty()).then(|| gpu_samples.iter().sum::<f32>() / gpu_samples.len() as f32),
+            draw_calls: (self.samples.iter().map(|s| s.draw_calls).sum::<u32>() as f32 / count).round() as u32
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        let frame_ms = self.average().frame_ms;
+        if frame_ms > 0.0 {
+            1000.0 / frame_ms
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        FrameStats::new()
+    }
+}
+
+/// two timestamps around the render pass
+pub struct GpuTimer {
+    query_pool: vk::QueryPool,
+    /// nanoseconds per tick
+    timestamp_period: f32,
+    timestamp_mask: u64,
+    /// nothing to read back until a frame has written both timestamps
+    written: bool
+}
+
+impl GpuTimer {
+    /// `None` if the graphics queue can't write timestamps
+    pub fn new(device: &ash::Device, properties: &vk::PhysicalDeviceProperties, timestamp_valid_bits: u32) -> Option<GpuTimer> {
+        if timestamp_valid_bits == 0 || properties.limits.timestamp_period == 0.0 {
+            return None;
+        }
+
+        unsafe {
+            let create_info = vk::QueryPoolCreateInfo::builder()
+                .query_type(vk::QueryType::TIMESTAMP)
+                .query_count(2)
+                .build();
+
+            Some(GpuTimer {
+                query_pool: device.create_query_pool(&create_info, None).unwrap(),
+                timestamp_period: properties.limits.timestamp_period,
+                timestamp_mask: if timestamp_valid_bits >= 64 { u64::MAX } else { (1 << timestamp_valid_bits) - 1 },
+                written: false
+            })
+        }
+    }
+
+    pub fn query_pool(&self) -> vk::QueryPool {
+        self.query_pool
+    }
+
+    pub fn begin(&self, device: &ash::Device, command_buffer: vk::CommandBuffer) {
+        unsafe {
+            device.cmd_reset_query_pool(command_buffer, self.query_pool, 0, 2);
+            device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::TOP_OF_PIPE, self.query_pool, 0);
+        }
+    }
+
+    pub fn end(&mut self, device: &ash::Device, command_buffer: vk::CommandBuffer) {
+        unsafe {
+            device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::BOTTOM_OF_PIPE, self.query_pool, 1);
+        }
+        self.written = true;
+    }
+
+    /// call once the frame's fence has signalled, returns milliseconds
+    pub fn read(&self, device: &ash::Device) -> Option<f32> {
+        if !self.written {
+            return None;
+        }
+
+        let mut timestamps = [0u64; 2];
+        unsafe {
+            device.get_query_pool_results(self.query_pool, 0, 2, &mut timestamps, vk::QueryResultFlags::TYPE_64).ok()?;
+        }
+
+        let ticks = (timestamps[1] & self.timestamp_mask).wrapping_sub(timestamps[0] & self.timestamp_mask) & self.timestamp_mask;
+        Some(ticks as f32 * self.timestamp_period / 1_000_000.0)
+    }
+}
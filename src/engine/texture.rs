@@ -0,0 +1,307 @@
+use ash::vk;
+use super::{allocator::{Allocation, SharedAllocator}, buffer::Buffer, Engine};
+
+/// a sampled rgba8 image with its own descriptor set, ready to draw sprites from
+pub struct Texture {
+    width: u32,
+    height: u32,
+    image: vk::Image,
+    view: vk::ImageView,
+    sampler: vk::Sampler,
+    allocation: Allocation,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set: vk::DescriptorSet,
+    // vulkan handles
+    device: ash::Device,
+    allocator: SharedAllocator
+}
+
+/// a piece of a texture, cheap to copy around
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    pub descriptor_set: vk::DescriptorSet,
+    /// u, v, width, height in 0..1
+    pub uv_rect: [f32; 4]
+}
+
+impl Texture {
+    /// `pixels` is tightly packed straight alpha rgba8, top row first
+    pub fn from_rgba(engine: &Engine, width: u32, height: u32, pixels: &[u8], name: &str) -> Texture {
+        // in usize so a big texture can't wrap the u32 and slip past the check
+        let size = (width as usize).checked_mul(height as usize).and_then(|p| p.checked_mul(4));
+        assert_eq!(Some(pixels.len()), size, "Texture data doesn't match its size");
+
+        let device = engine.device();
+        let allocator = engine.allocator();
+
+        unsafe {
+            let create_info = vk::ImageCreateInfo::builder()
+                .image_type(vk::ImageType::TYPE_2D)
+                // srgb since the swapchain is srgb too, sampling gives back linear values
+                .format(vk::Format::R8G8B8A8_SRGB)
+                .extent(vk::Extent3D {
+                    width,
+                    height,
+                    depth: 1
+                })
+                .mip_levels(1)
+                .array_layers(1)
+                .samples(vk::SampleCountFlags::TYPE_1)
+                .tiling(vk::ImageTiling::OPTIMAL)
+                .usage(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED)
+                .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                .initial_layout(vk::ImageLayout::UNDEFINED)
+                .build();
+
+            let image = device.create_image(&create_info, None).unwrap();
+            let allocation = allocator.borrow_mut().allocate_image(image, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+
+            let staging_buffer = Buffer::new(
+                pixels,
+                vk::BufferUsageFlags::TRANSFER_SRC,
+                device.clone(),
+                allocator.clone(),
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT
+            );
+
+            let command_buffer = engine.begin_single_exec_command();
+
+            transition_layout(&device, command_buffer, image, vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+            device.cmd_copy_buffer_to_image(
+                command_buffer,
+                staging_buffer.handle(),
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[
+                    vk::BufferImageCopy::builder()
+                        .buffer_offset(0)
+                        .buffer_row_length(0)
+                        .buffer_image_height(0)
+                        .image_subresource(vk::ImageSubresourceLayers {
+                            aspect_mask: vk::ImageAspectFlags::COLOR,
+                            mip_level: 0,
+                            base_array_layer: 0,
+                            layer_count: 1
+                        })
+                        .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+                        .image_extent(vk::Extent3D { width, height, depth: 1 })
+                        .build()
+                ]
+            );
+            transition_layout(&device, command_buffer, image, vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+
+            // waits for the queue so the staging buffer can go
+            engine.end_single_exec_command(command_buffer);
+            drop(staging_buffer);
+
+            let view_create_info = vk::ImageViewCreateInfo::builder()
+                .image(image)
+                .view_type(vk::ImageViewType::TYPE_2D)
+                .format(vk::Format::R8G8B8A8_SRGB)
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1
+                })
+                .build();
+
+            let view = device.create_image_view(&view_create_info, None).unwrap();
+
+            // nearest so pixel art stays crisp
+            let anisotropy = engine.gpu_features().sampler_anisotropy == vk::TRUE;
+            let sampler_create_info = vk::SamplerCreateInfo::builder()
+                .mag_filter(vk::Filter::NEAREST)
+                .min_filter(vk::Filter::NEAREST)
+                .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+                .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                .anisotropy_enable(anisotropy)
+                .max_anisotropy(if anisotropy { engine.max_sampler_anisotropy() } else { 1.0 })
+                .border_color(vk::BorderColor::INT_TRANSPARENT_BLACK)
+                .unnormalized_coordinates(false)
+                .compare_enable(false)
+                .min_lod(0.0)
+                .max_lod(0.0)
+                .build();
+
+            let sampler = device.create_sampler(&sampler_create_info, None).unwrap();
+
+            let descriptor_pool = {
+                let pool_sizes = [
+                    vk::DescriptorPoolSize::builder()
+                        .ty(vk::DescriptorType::SAMPLED_IMAGE)
+                        .descriptor_count(1)
+                        .build(),
+                    vk::DescriptorPoolSize::builder()
+                        .ty(vk::DescriptorType::SAMPLER)
+                        .descriptor_count(1)
+                        .build()
+                ];
+
+                let create_info = vk::DescriptorPoolCreateInfo::builder()
+                    .pool_sizes(&pool_sizes)
+                    .max_sets(1)
+                    .build();
+
+                device.create_descriptor_pool(&create_info, None).unwrap()
+            };
+
+            let descriptor_set = {
+                let create_info = vk::DescriptorSetAllocateInfo::builder()
+                    .descriptor_pool(descriptor_pool)
+                    .set_layouts(&[engine.texture_set_layout()])
+                    .build();
+
+                device.allocate_descriptor_sets(&create_info).unwrap()[0]
+            };
+
+            let image_info = [
+                vk::DescriptorImageInfo::builder()
+                    .image_view(view)
+                    .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                    .build()
+            ];
+            let sampler_info = [
+                vk::DescriptorImageInfo::builder()
+                    .sampler(sampler)
+                    .build()
+            ];
+
+            device.update_descriptor_sets(
+                &[
+                    vk::WriteDescriptorSet::builder()
+                        .dst_set(descriptor_set)
+                        .dst_binding(0)
+                        .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
+                        .image_info(&image_info)
+                        .build(),
+                    vk::WriteDescriptorSet::builder()
+                        .dst_set(descriptor_set)
+                        .dst_binding(1)
+                        .descriptor_type(vk::DescriptorType::SAMPLER)
+                        .image_info(&sampler_info)
+                        .build()
+                ],
+                &[]
+            );
+
+            engine.name_object(image, name);
+            engine.name_object(view, &format!("{name} view"));
+            engine.name_object(sampler, &format!("{name} sampler"));
+            engine.name_object(descriptor_pool, &format!("{name} pool"));
+            engine.name_object(descriptor_set, &format!("{name} descriptor set"));
+
+            Texture {
+                width,
+                height,
+                image,
+                view,
+                sampler,
+                allocation,
+                descriptor_pool,
+                descriptor_set,
+                device,
+                allocator
+            }
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// the whole texture as one sprite
+    pub fn sprite(&self) -> Sprite {
+        Sprite {
+            descriptor_set: self.descriptor_set,
+            uv_rect: [0.0, 0.0, 1.0, 1.0]
+        }
+    }
+
+    /// a piece of the texture, `x`, `y`, `width` and `height` are in pixels from the top left
+    pub fn sub_sprite(&self, x: u32, y: u32, width: u32, height: u32) -> Sprite {
+        Sprite {
+            descriptor_set: self.descriptor_set,
+            uv_rect: [
+                x as f32 / self.width as f32,
+                y as f32 / self.height as f32,
+                width as f32 / self.width as f32,
+                height as f32 / self.height as f32
+            ]
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            // frees the set too
+            self.device.destroy_descriptor_pool(self.descriptor_pool, None);
+            self.device.destroy_sampler(self.sampler, None);
+            self.device.destroy_image_view(self.view, None);
+            self.device.destroy_image(self.image, None);
+        }
+        self.allocator.borrow_mut().free(&self.allocation);
+    }
+}
+
+/// only knows the two transitions an upload needs
+fn transition_layout(
+    device: &ash::Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout
+) {
+    let (src_access, dst_access, src_stage, dst_stage) = match (old_layout, new_layout) {
+        (vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL) => (
+            vk::AccessFlags::empty(),
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            vk::PipelineStageFlags::TRANSFER
+        ),
+        (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER
+        ),
+        _ => panic!("Unsupported layout transition {old_layout:?} -> {new_layout:?}")
+    };
+
+    let barrier = vk::ImageMemoryBarrier::builder()
+        .old_layout(old_layout)
+        .new_layout(new_layout)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1
+        })
+        .src_access_mask(src_access)
+        .dst_access_mask(dst_access)
+        .build();
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            src_stage,
+            dst_stage,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[barrier]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use ash::vk;
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
 pub struct Vertex {
     pub position: [f32; 2]
 }
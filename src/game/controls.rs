@@ -0,0 +1,284 @@
+use std::{collections::{HashMap, HashSet}, fmt, fs, io, path::{Path, PathBuf}};
+use glfw::{GamepadAxis, GamepadButton, GamepadState, Key};
+use serde::Deserialize;
+use crate::engine::Engine;
+use super::Direction;
+
+/// everything the player can do, each one can be bound to any number of keys and gamepad buttons
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Control {
+    TurnNorth,
+    TurnSouth,
+    TurnWest,
+    TurnEast,
+    /// picks a menu item
+    Select,
+    Pause,
+    Restart,
+    /// back out of a menu, or to the title from a game
+    Quit,
+    /// next built in theme, works on any screen
+    CycleTheme
+}
+
+impl Control {
+    pub const ALL: [Control; 9] = [
+        Control::TurnNorth,
+        Control::TurnSouth,
+        Control::TurnWest,
+        Control::TurnEast,
+        Control::Select,
+        Control::Pause,
+        Control::Restart,
+        Control::Quit,
+        Control::CycleTheme
+    ];
+
+    fn default_keys(self) -> Vec<Key> {
+        match self {
+            Control::TurnNorth => vec![Key::W, Key::Up],
+            Control::TurnSouth => vec![Key::S, Key::Down],
+            Control::TurnWest => vec![Key::A, Key::Left],
+            Control::TurnEast => vec![Key::D, Key::Right],
+            Control::Select => vec![Key::Enter, Key::KpEnter, Key::Space],
+            Control::Pause => vec![Key::P, Key::Escape],
+            Control::Restart => vec![Key::R],
+            Control::Quit => vec![Key::Q, Key::Backspace],
+            Control::CycleTheme => vec![Key::T]
+        }
+    }
+
+    fn default_buttons(self) -> Vec<GamepadButton> {
+        match self {
+            Control::TurnNorth => vec![GamepadButton::ButtonDpadUp],
+            Control::TurnSouth => vec![GamepadButton::ButtonDpadDown],
+            Control::TurnWest => vec![GamepadButton::ButtonDpadLeft],
+            Control::TurnEast => vec![GamepadButton::ButtonDpadRight],
+            Control::Select => vec![GamepadButton::ButtonA],
+            Control::Pause => vec![GamepadButton::ButtonStart],
+            Control::Restart => vec![GamepadButton::ButtonY],
+            Control::Quit => vec![GamepadButton::ButtonBack],
+            Control::CycleTheme => vec![GamepadButton::ButtonRightBumper]
+        }
+    }
+
+    /// the turn this control makes, if it is one
+    pub fn direction(self) -> Option<Direction> {
+        match self {
+            Control::TurnNorth => Some(Direction::North),
+            Control::TurnSouth => Some(Direction::South),
+            Control::TurnWest => Some(Direction::West),
+            Control::TurnEast => Some(Direction::East),
+            _ => None
+        }
+    }
+}
+
+/// maps keys, gamepad buttons and the left stick to controls, rebindable from a toml file:
+///
+/// ```toml
+/// deadzone = 0.5
+///
+/// [keys]
+/// turn_north = ["i", "up"]
+/// pause = ["space"]
+///
+/// [buttons]
+/// select = ["a", "x"]
+/// ```
+///
+/// anything left out keeps its default binding
+pub struct Controls {
+    keys: HashMap<Control, Vec<Key>>,
+    buttons: HashMap<Control, Vec<GamepadButton>>,
+    /// how far the stick has to move before it counts, 0 to 1
+    deadzone: f32,
+    /// what the gamepad is holding this frame and last frame, keys use glfw's events instead
+    gamepad_held: HashSet<Control>,
+    prev_gamepad_held: HashSet<Control>
+}
+
+#[derive(Debug)]
+pub enum ControlsError {
+    Io(PathBuf, io::Error),
+    Toml(toml::de::Error)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ControlsFile {
+    keys: HashMap<Control, Vec<KeyName>>,
+    buttons: HashMap<Control, Vec<ButtonName>>,
+    deadzone: Option<f32>
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+struct KeyName(Key);
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+struct ButtonName(GamepadButton);
+
+impl Controls {
+    pub const DEFAULT_DEADZONE: f32 = 0.5;
+
+    /// wasd, arrow keys and a standard gamepad layout
+    pub fn new() -> Controls {
+        Controls {
+            keys: Control::ALL.into_iter().map(|c| (c, c.default_keys())).collect(),
+            buttons: Control::ALL.into_iter().map(|c| (c, c.default_buttons())).collect(),
+            deadzone: Controls::DEFAULT_DEADZONE,
+            gamepad_held: HashSet::new(),
+            prev_gamepad_held: HashSet::new()
+        }
+    }
+
+    /// the defaults with whatever `path` rebinds on top
+    pub fn load(path: &Path) -> Result<Controls, ControlsError> {
+        let text = fs::read_to_string(path).map_err(|e| ControlsError::Io(path.to_path_buf(), e))?;
+        let file: ControlsFile = toml::from_str(&text).map_err(ControlsError::Toml)?;
+
+        let mut controls = Controls::new();
+        for (control, keys) in file.keys {
+            controls.keys.insert(control, keys.into_iter().map(|k| k.0).collect());
+        }
+        for (control, buttons) in file.buttons {
+            controls.buttons.insert(control, buttons.into_iter().map(|b| b.0).collect());
+        }
+        if let Some(deadzone) = file.deadzone {
+            controls.deadzone = deadzone.clamp(0.0, 1.0);
+        }
+
+        Ok(controls)
+    }
+
+    /// reads the gamepad, call once a frame before asking about any controls
+    pub fn update(&mut self, engine: &Engine) {
+        self.prev_gamepad_held = std::mem::take(&mut self.gamepad_held);
+
+        let Some(gamepad) = engine.gamepad_state() else { return };
+        for control in Control::ALL {
+            let button_down = self.buttons[&control].iter().any(|b| gamepad.get_button_state(*b) == glfw::Action::Press);
+            if button_down {
+                self.gamepad_held.insert(control);
+            }
+        }
+        if let Some(control) = self.stick_control(&gamepad) {
+            self.gamepad_held.insert(control);
+        }
+    }
+
+    /// the left stick as a turn, whichever axis is pushed further wins
+    fn stick_control(&self, gamepad: &GamepadState) -> Option<Control> {
+        let x = gamepad.get_axis(GamepadAxis::AxisLeftX);
+        // glfw has y going down
+        let y = gamepad.get_axis(GamepadAxis::AxisLeftY);
+
+        if x.abs().max(y.abs()) < self.deadzone {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x > 0.0 { Control::TurnEast } else { Control::TurnWest })
+        } else {
+            Some(if y > 0.0 { Control::TurnSouth } else { Control::TurnNorth })
+        }
+    }
+
+    /// true on the frame the control went down, held keys don't repeat
+    pub fn pressed(&self, engine: &Engine, control: Control) -> bool {
+        self.keys[&control].iter().any(|k| engine.key_pressed(*k))
+            || (self.gamepad_held.contains(&control) && !self.prev_gamepad_held.contains(&control))
+    }
+
+    /// true for as long as the control is down
+    pub fn held(&self, engine: &Engine, control: Control) -> bool {
+        self.keys[&control].iter().any(|k| engine.window().get_key(*k) == glfw::Action::Press)
+            || self.gamepad_held.contains(&control)
+    }
+
+    /// the first turn being held, if any
+    pub fn held_direction(&self, engine: &Engine) -> Option<Direction> {
+        Control::ALL.into_iter()
+            .filter(|c| self.held(engine, *c))
+            .find_map(Control::direction)
+    }
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Controls::new()
+    }
+}
+
+/// lower case names for everything worth binding
+const KEY_NAMES: [(&str, Key); 70] = [
+    ("a", Key::A), ("b", Key::B), ("c", Key::C), ("d", Key::D), ("e", Key::E), ("f", Key::F), ("g", Key::G),
+    ("h", Key::H), ("i", Key::I), ("j", Key::J), ("k", Key::K), ("l", Key::L), ("m", Key::M), ("n", Key::N),
+    ("o", Key::O), ("p", Key::P), ("q", Key::Q), ("r", Key::R), ("s", Key::S), ("t", Key::T), ("u", Key::U),
+    ("v", Key::V), ("w", Key::W), ("x", Key::X), ("y", Key::Y), ("z", Key::Z),
+    ("0", Key::Num0), ("1", Key::Num1), ("2", Key::Num2), ("3", Key::Num3), ("4", Key::Num4),
+    ("5", Key::Num5), ("6", Key::Num6), ("7", Key::Num7), ("8", Key::Num8), ("9", Key::Num9),
+    ("kp_0", Key::Kp0), ("kp_1", Key::Kp1), ("kp_2", Key::Kp2), ("kp_3", Key::Kp3), ("kp_4", Key::Kp4),
+    ("kp_5", Key::Kp5), ("kp_6", Key::Kp6), ("kp_7", Key::Kp7), ("kp_8", Key::Kp8), ("kp_9", Key::Kp9),
+    ("kp_enter", Key::KpEnter),
+    ("up", Key::Up), ("down", Key::Down), ("left", Key::Left), ("right", Key::Right),
+    ("space", Key::Space), ("enter", Key::Enter), ("escape", Key::Escape), ("backspace", Key::Backspace),
+    ("tab", Key::Tab), ("left_shift", Key::LeftShift), ("right_shift", Key::RightShift),
+    ("left_control", Key::LeftControl), ("right_control", Key::RightControl),
+    ("left_alt", Key::LeftAlt), ("right_alt", Key::RightAlt),
+    ("home", Key::Home), ("end", Key::End), ("page_up", Key::PageUp), ("page_down", Key::PageDown),
+    ("insert", Key::Insert), ("delete", Key::Delete), ("comma", Key::Comma), ("period", Key::Period)
+];
+
+/// xbox style names, glfw maps other pads onto the same layout
+const BUTTON_NAMES: [(&str, GamepadButton); 15] = [
+    ("a", GamepadButton::ButtonA),
+    ("b", GamepadButton::ButtonB),
+    ("x", GamepadButton::ButtonX),
+    ("y", GamepadButton::ButtonY),
+    ("left_bumper", GamepadButton::ButtonLeftBumper),
+    ("right_bumper", GamepadButton::ButtonRightBumper),
+    ("back", GamepadButton::ButtonBack),
+    ("start", GamepadButton::ButtonStart),
+    ("guide", GamepadButton::ButtonGuide),
+    ("left_thumb", GamepadButton::ButtonLeftThumb),
+    ("right_thumb", GamepadButton::ButtonRightThumb),
+    ("dpad_up", GamepadButton::ButtonDpadUp),
+    ("dpad_down", GamepadButton::ButtonDpadDown),
+    ("dpad_left", GamepadButton::ButtonDpadLeft),
+    ("dpad_right", GamepadButton::ButtonDpadRight)
+];
+
+impl TryFrom<String> for KeyName {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        KEY_NAMES.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&value))
+            .map(|(_, key)| KeyName(*key))
+            .ok_or_else(|| format!("unknown key {value}"))
+    }
+}
+
+impl TryFrom<String> for ButtonName {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        BUTTON_NAMES.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&value))
+            .map(|(_, button)| ButtonName(*button))
+            .ok_or_else(|| format!("unknown gamepad button {value}"))
+    }
+}
+
+impl fmt::Display for ControlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControlsError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            ControlsError::Toml(e) => write!(f, "{e}")
+        }
+    }
+}
+
+impl std::error::Error for ControlsError {}
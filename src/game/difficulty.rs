@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Insane];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Insane => "insane"
+        }
+    }
+
+    /// case insensitive
+    pub fn parse(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL.into_iter().find(|d| d.name().eq_ignore_ascii_case(name))
+    }
+
+    /// ticks per second with no apples eaten
+    pub fn base_tick_rate(self) -> f32 {
+        match self {
+            Difficulty::Easy => 5.0,
+            Difficulty::Normal => 8.0,
+            Difficulty::Hard => 11.0,
+            Difficulty::Insane => 15.0
+        }
+    }
+
+    /// how much faster each apple makes the snake when the speed curve is on
+    pub fn tick_rate_per_apple(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.1,
+            Difficulty::Normal => 0.2,
+            Difficulty::Hard => 0.3,
+            Difficulty::Insane => 0.4
+        }
+    }
+
+    /// the speed curve flattens out here
+    pub fn max_tick_rate(self) -> f32 {
+        match self {
+            Difficulty::Easy => 8.0,
+            Difficulty::Normal => 13.0,
+            Difficulty::Hard => 18.0,
+            Difficulty::Insane => 25.0
+        }
+    }
+}
+
+/// how fast the snake goes, part of the game state so saves and replays play back at the same speed
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Speed {
+    pub difficulty: Difficulty,
+    /// speed up a little with every apple
+    pub speed_curve: bool,
+    pub apples_eaten: u32
+}
+
+impl Speed {
+    pub fn new(difficulty: Difficulty, speed_curve: bool) -> Speed {
+        Speed {
+            difficulty,
+            speed_curve,
+            apples_eaten: 0
+        }
+    }
+
+    /// ticks per second right now
+    pub fn tick_rate(&self) -> f32 {
+        let base = self.difficulty.base_tick_rate();
+        if self.speed_curve {
+            (base + self.apples_eaten as f32 * self.difficulty.tick_rate_per_apple()).min(self.difficulty.max_tick_rate())
+        } else {
+            base
+        }
+    }
+
+    pub fn ate_apple(&mut self) {
+        self.apples_eaten += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn after_apples(difficulty: Difficulty, speed_curve: bool, apples: u32) -> f32 {
+        let mut speed = Speed::new(difficulty, speed_curve);
+        (0..apples).for_each(|_| speed.ate_apple());
+        speed.tick_rate()
+    }
+
+    #[test]
+    fn flat_without_the_curve() {
+        for difficulty in Difficulty::ALL {
+            assert_eq!(after_apples(difficulty, false, 0), difficulty.base_tick_rate());
+            assert_eq!(after_apples(difficulty, false, 50), difficulty.base_tick_rate());
+        }
+    }
+
+    #[test]
+    fn curve_speeds_up_per_apple() {
+        assert_eq!(after_apples(Difficulty::Normal, true, 0), 8.0);
+        assert!((after_apples(Difficulty::Normal, true, 10) - 10.0).abs() < 1e-4);
+        assert!(after_apples(Difficulty::Hard, true, 3) > after_apples(Difficulty::Hard, true, 2));
+    }
+
+    #[test]
+    fn curve_stops_at_the_max() {
+        for difficulty in Difficulty::ALL {
+            assert_eq!(after_apples(difficulty, true, 1000), difficulty.max_tick_rate());
+        }
+    }
+
+    #[test]
+    fn parse_is_case_insensitive() {
+        assert_eq!(Difficulty::parse("HaRd"), Some(Difficulty::Hard));
+        assert_eq!(Difficulty::parse("nightmare"), None);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{cmp::Reverse, fs, path::PathBuf};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use crate::engine::paths::data_dir;
+use super::difficulty::{Difficulty, Speed};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: u32,
+    pub difficulty: Difficulty,
+    pub speed_curve: bool
+}
+
+/// the best few games, best first, kept in `high_scores.toml` in the data dir
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    scores: Vec<HighScore>
+}
+
+impl HighScores {
+    pub const MAX_ENTRIES: usize = 10;
+
+    fn path() -> Option<PathBuf> {
+        data_dir().map(|dir| dir.join("high_scores.toml"))
+    }
+
+    /// starts empty if there's no file yet or it can't be read
+    pub fn load() -> HighScores {
+        let Some(path) = HighScores::path() else { return HighScores::default() };
+        let Ok(text) = fs::read_to_string(&path) else { return HighScores::default() };
+
+        match toml::from_str::<HighScores>(&text) {
+            Ok(mut high_scores) => {
+                high_scores.scores.sort_by_key(|s| Reverse(s.score));
+                high_scores.scores.truncate(HighScores::MAX_ENTRIES);
+                debug!(entries = high_scores.scores.len(); "Loaded high scores");
+                high_scores
+            }
+            Err(e) => {
+                warn!("Failed to read high scores from {}: {e}", path.display());
+                HighScores::default()
+            }
+        }
+    }
+
+    /// failing to save just loses this session's scores
+    pub fn save(&self) {
+        let Some(path) = HighScores::path() else { return };
+
+        let text = toml::to_string(self).unwrap();
+        let result = fs::create_dir_all(path.parent().unwrap()).and_then(|_| fs::write(&path, text));
+        if let Err(e) = result {
+            warn!("Failed to save high scores to {}: {e}", path.display());
+        }
+    }
+
+    pub fn scores(&self) -> &[HighScore] {
+        &self.scores
+    }
+
+    /// adds the game if it made the list and returns its place, 0 being the best
+    pub fn insert(&mut self, score: u32, speed: &Speed) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+
+        // after any equal scores so older ones keep their place
+        let rank = self.scores.partition_point(|s| s.score >= score);
+        if rank >= HighScores::MAX_ENTRIES {
+            return None;
+        }
+
+        self.scores.insert(rank, HighScore {
+            score,
+            difficulty: speed.difficulty,
+            speed_curve: speed.speed_curve
+        });
+        self.scores.truncate(HighScores::MAX_ENTRIES);
+
+        Some(rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speed() -> Speed {
+        Speed::new(Difficulty::Hard, true)
+    }
+
+    fn scores(high_scores: &HighScores) -> Vec<u32> {
+        high_scores.scores().iter().map(|s| s.score).collect()
+    }
+
+    #[test]
+    fn kept_best_first() {
+        let mut high_scores = HighScores::default();
+
+        assert_eq!(high_scores.insert(5, &speed()), Some(0));
+        assert_eq!(high_scores.insert(9, &speed()), Some(0));
+        assert_eq!(high_scores.insert(7, &speed()), Some(1));
+        assert_eq!(scores(&high_scores), [9, 7, 5]);
+        assert_eq!(high_scores.scores()[0].difficulty, Difficulty::Hard);
+        assert!(high_scores.scores()[0].speed_curve);
+    }
+
+    #[test]
+    fn ties_go_after_older_scores() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert(5, &Speed::new(Difficulty::Easy, false));
+
+        assert_eq!(high_scores.insert(5, &speed()), Some(1));
+        assert_eq!(high_scores.scores()[0].difficulty, Difficulty::Easy);
+    }
+
+    #[test]
+    fn zero_never_counts() {
+        let mut high_scores = HighScores::default();
+
+        assert_eq!(high_scores.insert(0, &speed()), None);
+        assert!(high_scores.scores().is_empty());
+    }
+
+    #[test]
+    fn only_the_best_are_kept() {
+        let mut high_scores = HighScores::default();
+        for score in 1..=HighScores::MAX_ENTRIES as u32 {
+            high_scores.insert(score * 10, &speed());
+        }
+
+        // worse than everything on a full list
+        assert_eq!(high_scores.insert(5, &speed()), None);
+        // the same as the worst one goes after it so it doesn't make it either
+        assert_eq!(high_scores.insert(10, &speed()), None);
+
+        assert_eq!(high_scores.insert(55, &speed()), Some(5));
+        assert_eq!(high_scores.scores().len(), HighScores::MAX_ENTRIES);
+        assert_eq!(scores(&high_scores).last(), Some(&20));
+    }
+}
@@ -1,22 +1,73 @@
 pub mod tile;
+pub mod theme;
+pub mod difficulty;
+pub mod pause;
+pub mod high_scores;
+pub mod screen;
+pub mod controls;
 
-use std::{mem::MaybeUninit, collections::HashMap};
+use std::{mem::MaybeUninit, collections::{HashMap, VecDeque}};
 
-use ash::vk;
+use log::debug;
 use rand::Rng;
 
-use crate::engine::Engine;
-use self::tile::{Tile, TileState};
+use crate::engine::{draw::Layer, sprite_sheet::SpriteSheet, text::text_size, texture::Sprite, Engine};
+use self::{difficulty::Speed, theme::Theme, tile::{Tile, TileState}};
 
+/// the board is `BOARD_SIZE` cells square, cell `[x, y]` has its top left corner at
+/// `[x, y] * CELL_PX` in the engine's pixel space so `[0, 0]` is the top left cell
+/// and y grows down the screen, the same way everything else the engine draws does
 pub struct Game {
     tiles: HashMap<[i8; 2], Tile>,
     current_direction: Direction,
     head: [i8; 2],
     tail: ([i8; 2], Direction),
     /// this is only here so that the tail knows where to turn
-    directions: Vec<Direction>
+    directions: Vec<Direction>,
+    /// every snake tile from head to tail, used to fade the tail out
+    body: VecDeque<[i8; 2]>,
+    /// `body` before the last tick, the snake is drawn sliding from this to `body`
+    prev_body: VecDeque<[i8; 2]>,
+    /// flat colours are used for anything without a sprite
+    sprites: TileSprites,
+    theme: Theme,
+    speed: Speed
 }
 
+/// sprite art for the board, any of these can be left out
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TileSprites {
+    pub empty: Option<Sprite>,
+    pub head: Option<Sprite>,
+    pub body: Option<Sprite>,
+    pub apple: Option<Sprite>,
+    /// repeated once per cell along each edge
+    pub wall: Option<Sprite>
+}
+
+impl TileSprites {
+    /// picks out the `empty`, `head`, `body`, `apple` and `wall` sprites or animations, `seconds` drives the animations
+    pub fn from_sheet(sheet: &SpriteSheet, seconds: f32) -> TileSprites {
+        TileSprites {
+            empty: sheet.frame("empty", seconds),
+            head: sheet.frame("head", seconds),
+            body: sheet.frame("body", seconds),
+            apple: sheet.frame("apple", seconds),
+            wall: sheet.frame("wall", seconds)
+        }
+    }
+}
+
+/// what happened to the snake this tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickOutcome {
+    Moved,
+    AteApple,
+    /// ran into the wall or itself, the board is left as it was
+    Died
+}
+
+/// north is up the screen, see `Game` for how cells map to pixels
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub enum Direction {
     North,
@@ -25,15 +76,32 @@ pub enum Direction {
     East
 }
 
+impl Direction {
+    /// one cell this way, y counts down the screen so north is -y
+    pub fn offset(self) -> [i8; 2] {
+        match self {
+            Direction::North => [0, -1],
+            Direction::South => [0, 1],
+            Direction::West => [-1, 0],
+            Direction::East => [1, 0]
+        }
+    }
+}
+
 impl Game {
-    pub fn new(engine: &Engine) -> Game {
+    pub const BOARD_SIZE: i8 = 10;
+    pub const CELL_PX: f32 = 80.0;
+    const TAIL_ALPHA: f32 = 0.35;
+
+    pub fn new(engine: &Engine, speed: Speed) -> Game {
         let mut tiles = HashMap::with_capacity(100);
 
-        for x in 0..10i8 {
-            for y in 0..10i8 {
+        for x in 0..Game::BOARD_SIZE {
+            for y in 0..Game::BOARD_SIZE {
                 tiles.insert([x, y], Tile::new(
-                    [x as f32 * 80.0, y as f32 * 80.0],
+                    [x as f32 * Game::CELL_PX, y as f32 * Game::CELL_PX],
                     TileState::Empty,
+                    &format!("tile[{x},{y}]"),
                     engine
                 ));
             }
@@ -50,7 +118,12 @@ impl Game {
             current_direction: Direction::East,
             head: [4, 5],
             tail: ([2, 5], Direction::East),
-            directions: Vec::new()
+            directions: Vec::new(),
+            body: VecDeque::from([[4, 5], [3, 5], [2, 5]]),
+            prev_body: VecDeque::from([[4, 5], [3, 5], [2, 5]]),
+            sprites: TileSprites::default(),
+            theme: Theme::classic(),
+            speed
         }
     }
 
@@ -76,15 +149,11 @@ impl Game {
         }
     }
 
-    pub fn tick(&mut self) {
+    pub fn tick(&mut self) -> TickOutcome {
+        self.prev_body = self.body.clone();
         let mut ate_apple = false;
 
-        let forward = match self.current_direction {
-            Direction::North => [0, 1],
-            Direction::South => [0, -1],
-            Direction::West => [-1, 0],
-            Direction::East => [1, 0]
-        };
+        let forward = self.current_direction.offset();
 
         let head_pos = self.head;
 
@@ -98,24 +167,21 @@ impl Game {
                     ate_apple = true;
                 }
                 TileState::Snake => {
-                    panic!("hit snake");
+                    debug!(head:? = self.head; "Hit snake");
+                    return TickOutcome::Died;
                 }
             }
 
             // this should be only accessible if the tile is empty or apple
             forward_tile.tile_state = TileState::Snake;
         } else {
-            panic!("hit border");
+            debug!(head:? = self.head; "Hit border");
+            return TickOutcome::Died;
         }
 
         let tail_pos = self.tail.0;
 
-        let tail_forward = match self.tail.1 {
-            Direction::North => [0, 1],
-            Direction::South => [0, -1],
-            Direction::West => [-1, 0],
-            Direction::East => [1, 0]
-        };
+        let tail_forward = self.tail.1.offset();
 
         if !ate_apple {
             if let Some(forward_tile) = self.tiles.get(&[
@@ -139,36 +205,322 @@ impl Game {
             self.tiles.get_mut(&[tail_pos[0], tail_pos[1]]).unwrap().tile_state = TileState::Empty;
         }
 
-        let tail_forward = match self.tail.1 {
-            Direction::North => [0, 1],
-            Direction::South => [0, -1],
-            Direction::West => [-1, 0],
-            Direction::East => [1, 0]
-        };
+        let tail_forward = self.tail.1.offset();
 
         self.head = [head_pos[0] + forward[0], head_pos[1] + forward[1]];
+        self.body.push_front(self.head);
         if !ate_apple {
             self.tail.0 = [tail_pos[0] + tail_forward[0], tail_pos[1] + tail_forward[1]];
+            self.body.pop_back();
         }
 
         if ate_apple {
+            self.speed.ate_apple();
             loop {
-                let rand_x: i8 = rand::thread_rng().gen_range(0..10);
-                let rand_y: i8 = rand::thread_rng().gen_range(0..10);
+                let rand_x: i8 = rand::thread_rng().gen_range(0..Game::BOARD_SIZE);
+                let rand_y: i8 = rand::thread_rng().gen_range(0..Game::BOARD_SIZE);
 
                 if self.tiles.get(&[rand_x, rand_y]).unwrap().tile_state == TileState::Empty {
                     self.tiles.get_mut(&[rand_x, rand_y]).unwrap().tile_state = TileState::Apple;
+                    debug!(head:? = self.head, apple:? = [rand_x, rand_y]; "Ate apple");
                     break;
                 }
             }
+
+            TickOutcome::AteApple
+        } else {
+            TickOutcome::Moved
+        }
+    }
+
+    /// apples eaten this game
+    pub fn score(&self) -> u32 {
+        self.speed.apples_eaten
+    }
+
+    pub fn speed(&self) -> &Speed {
+        &self.speed
+    }
+
+    /// textures have to outlive the game since sprites only borrow them by handle
+    pub fn set_sprites(&mut self, sprites: TileSprites) {
+        self.sprites = sprites;
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// also hands the background and hud colours to the engine
+    pub fn set_theme(&mut self, theme: Theme, engine: &mut Engine) {
+        engine.set_clear_color(theme.background.0);
+        engine.set_hud_colors(theme.hud_text.0, theme.hud_background.0);
+        self.theme = theme;
+    }
+
+    /// the colour of a body segment, fading from the head colour to the tail colour
+    /// and from solid to `TAIL_ALPHA`
+    fn segment_color(&self, index: usize) -> [f32; 4] {
+        let t = if self.body.len() <= 1 {
+            0.0
+        } else {
+            index as f32 / (self.body.len() - 1) as f32
+        };
+
+        self.theme.snake_color(t).with_alpha(1.0 - t * (1.0 - Game::TAIL_ALPHA)).0
+    }
+
+    /// width and height of the whole board in world pixels
+    pub fn board_px() -> [f32; 2] {
+        [Game::BOARD_SIZE as f32 * Game::CELL_PX; 2]
+    }
+
+    /// top left pixel of the square drawn inside `cell`
+    fn cell_to_px(cell: [f32; 2]) -> [f32; 2] {
+        [
+            cell[0] * Game::CELL_PX + 4.0,
+            cell[1] * Game::CELL_PX + 4.0
+        ]
+    }
+
+    /// the snake as a line through its cells with the head and tail slid `tick_fraction` of the way
+    /// from where they were last tick, so it moves smoothly while the renderer runs faster than the game
+    fn snake_points(body: &VecDeque<[i8; 2]>, prev_body: &VecDeque<[i8; 2]>, tick_fraction: f32) -> Vec<[f32; 2]> {
+        let lerp = |from: [i8; 2], to: [i8; 2]| [
+            from[0] as f32 + (to[0] - from[0]) as f32 * tick_fraction,
+            from[1] as f32 + (to[1] - from[1]) as f32 * tick_fraction
+        ];
+        let cell = |c: &[i8; 2]| [c[0] as f32, c[1] as f32];
+
+        let mut points = Vec::with_capacity(body.len() + 1);
+        points.push(lerp(prev_body[0], body[0]));
+        points.extend(body.iter().skip(1).map(cell));
+        // the old tail end shrinks into the new one, or stays put if the snake just grew
+        points.push(lerp(*prev_body.back().unwrap(), *body.back().unwrap()));
+
+        points
+    }
+
+    /// `tick_fraction` is how far into the current tick we are, 0 to 1
+    pub fn draw(&self, engine: &mut Engine, tick_fraction: f32) {
+        self.draw_walls(engine);
+
+        for x in 0..Game::BOARD_SIZE {
+            for y in 0..Game::BOARD_SIZE {
+                let tile = &self.tiles[&[x, y]];
+                // the snake is drawn on its own below so the board shows through under it
+                let (layer, color, sprite) = match tile.tile_state {
+                    TileState::Empty | TileState::Snake => (Layer::Board, self.theme.grid.0, self.sprites.empty),
+                    TileState::Apple => (Layer::Snake, self.theme.apple.0, self.sprites.apple)
+                };
+                tile.draw_with(engine, layer, color, sprite);
+            }
+        }
+
+        let points = Game::snake_points(&self.body, &self.prev_body, tick_fraction.clamp(0.0, 1.0));
+        if self.sprites.head.is_some() || self.sprites.body.is_some() {
+            self.draw_snake_sprites(engine, &points);
+        } else {
+            self.draw_snake_shape(engine, &points);
+        }
+
+        self.draw_hud(engine);
+    }
+
+    /// the 4px gap around the edge of the board
+    fn draw_walls(&self, engine: &mut Engine) {
+        let [width, height] = Game::board_px();
+
+        let Some(sprite) = self.sprites.wall else {
+            let wall = self.theme.wall.0;
+            engine.draw_rect(Layer::Board, [0.0, 0.0], [width, 4.0], wall);
+            engine.draw_rect(Layer::Board, [0.0, height - 4.0], [width, 4.0], wall);
+            engine.draw_rect(Layer::Board, [0.0, 4.0], [4.0, height - 8.0], wall);
+            engine.draw_rect(Layer::Board, [width - 4.0, 4.0], [4.0, height - 8.0], wall);
+            return;
+        };
+
+        // one sprite per cell so the art doesn't get stretched along the whole edge
+        let white = [1.0, 1.0, 1.0, 1.0];
+        for i in 0..Game::BOARD_SIZE {
+            let along = i as f32 * Game::CELL_PX;
+            engine.draw_sprite(Layer::Board, sprite, [along, 0.0], [Game::CELL_PX, 4.0], white);
+            engine.draw_sprite(Layer::Board, sprite, [along, height - 4.0], [Game::CELL_PX, 4.0], white);
+            engine.draw_sprite(Layer::Board, sprite, [0.0, along], [4.0, Game::CELL_PX], white);
+            engine.draw_sprite(Layer::Board, sprite, [width - 4.0, along], [4.0, Game::CELL_PX], white);
+        }
+    }
+
+    /// difficulty and speed in the top right, the engine overlay has the other corner
+    fn draw_hud(&self, engine: &mut Engine) {
+        let mut text = format!("{}\nSPEED {:.1}", self.speed.difficulty.name(), self.speed.tick_rate());
+        if self.speed.speed_curve {
+            text += &format!("\nAPPLES {}", self.speed.apples_eaten);
+        }
+
+        let scale = 3.0;
+        let (width, height) = text_size(&text);
+        let size = [(width + 4) as f32 * scale, (height + 4) as f32 * scale];
+        let position = [engine.viewport_size()[0] - 4.0 - size[0], 4.0];
+
+        engine.draw_rect(Layer::Hud, position, size, self.theme.hud_background.0);
+        engine.draw_text(Layer::Hud, &text, [position[0] + 2.0 * scale, position[1] + 2.0 * scale], scale, self.theme.hud_text.0);
+    }
+
+    /// a flat coloured band through the points, each piece covers its own cell up to where the
+    /// next one starts so nothing overlaps and the faded tail doesn't get darker at the joins
+    fn draw_snake_shape(&self, engine: &mut Engine, points: &[[f32; 2]]) {
+        for (index, pair) in points.windows(2).enumerate() {
+            let (from, to) = (Game::cell_to_px(pair[0]), Game::cell_to_px(pair[1]));
+            let color = self.segment_color(index);
+
+            let (position, size) = if from[0] != to[0] {
+                let x = if to[0] > from[0] { from[0] } else { to[0] + 72.0 };
+                ([x, from[1]], [(to[0] - from[0]).abs(), 72.0])
+            } else if from[1] != to[1] {
+                let y = if to[1] > from[1] { from[1] } else { to[1] + 72.0 };
+                ([from[0], y], [72.0, (to[1] - from[1]).abs()])
+            } else {
+                continue;
+            };
+
+            engine.draw_rect(Layer::Snake, position, size, color);
+        }
+
+        let tail = Game::cell_to_px(*points.last().unwrap());
+        engine.draw_rect(Layer::Snake, tail, [72.0, 72.0], self.segment_color(self.body.len() - 1));
+    }
+
+    /// one sprite per cell, tail first so the head ends up on top
+    fn draw_snake_sprites(&self, engine: &mut Engine, points: &[[f32; 2]]) {
+        for (index, point) in Game::sprite_points(&self.body, &self.prev_body, points) {
+            let sprite = if index == 0 {
+                self.sprites.head.or(self.sprites.body)
+            } else {
+                self.sprites.body.or(self.sprites.head)
+            };
+            let alpha = self.segment_color(index.min(self.body.len() - 1))[3];
+
+            engine.draw_sprite(Layer::Snake, sprite.unwrap(), Game::cell_to_px(point), [72.0, 72.0], [1.0, 1.0, 1.0, alpha]);
+        }
+    }
+
+    /// which of `snake_points` get a sprite, with their index, tail first.
+    /// the sliding tail and the last cell both get one so there's no gap between them, unless the tail
+    /// isn't moving because the snake just grew, then they're the same cell. a one cell snake's tail is its head
+    fn sprite_points(body: &VecDeque<[i8; 2]>, prev_body: &VecDeque<[i8; 2]>, points: &[[f32; 2]]) -> Vec<(usize, [f32; 2])> {
+        let skipped = if body.len() == 1 {
+            Some(points.len() - 1)
+        } else if prev_body.back() == body.back() {
+            Some(points.len() - 2)
+        } else {
+            None
+        };
+
+        points.iter()
+            .copied()
+            .enumerate()
+            .rev()
+            .filter(|(index, _)| Some(*index) != skipped)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{camera::Camera, projection::Mat4};
+    use super::*;
+
+    const DIRECTIONS: [Direction; 4] = [Direction::North, Direction::South, Direction::West, Direction::East];
+
+    /// `matrix * [x, y, 0, 1]`, what the vertex shaders do with `projection`
+    fn to_clip(matrix: &Mat4, point: [f32; 2]) -> [f32; 2] {
+        let row = |r: usize| matrix[0][r] * point[0] + matrix[1][r] * point[1] + matrix[3][r];
+        [row(0) / row(3), row(1) / row(3)]
+    }
+
+    /// the camera the way main sets it up, fitted around a `size` cell board
+    fn board_camera(size: [i8; 2], viewport: [f32; 2]) -> Mat4 {
+        let board_px = [size[0] as f32 * Game::CELL_PX, size[1] as f32 * Game::CELL_PX];
+        let mut camera = Camera::new([0.0, 0.0]);
+        camera.fit([0.0, 0.0], board_px, viewport);
+        camera.view_projection(viewport)
+    }
+
+    #[test]
+    fn directions_move_the_right_way_on_screen() {
+        for size in [[5, 5], [10, 10], [7, 13]] {
+            for viewport in [[800.0, 800.0], [1280.0, 720.0]] {
+                let view_projection = board_camera(size, viewport);
+                let cell = [size[0] / 2, size[1] / 2];
+                let cell_clip = to_clip(&view_projection, Game::cell_to_px([cell[0] as f32, cell[1] as f32]));
+
+                for direction in DIRECTIONS {
+                    let offset = direction.offset();
+                    let next = [(cell[0] + offset[0]) as f32, (cell[1] + offset[1]) as f32];
+                    let [dx, dy] = {
+                        let next_clip = to_clip(&view_projection, Game::cell_to_px(next));
+                        [next_clip[0] - cell_clip[0], next_clip[1] - cell_clip[1]]
+                    };
+
+                    let context = format!("{direction:?} on a {size:?} board in a {viewport:?} window");
+                    match direction {
+                        Direction::North => assert!(dy < 0.0 && dx == 0.0, "{context} went {dx}, {dy}"),
+                        Direction::South => assert!(dy > 0.0 && dx == 0.0, "{context} went {dx}, {dy}"),
+                        Direction::West => assert!(dx < 0.0 && dy == 0.0, "{context} went {dx}, {dy}"),
+                        Direction::East => assert!(dx > 0.0 && dy == 0.0, "{context} went {dx}, {dy}")
+                    }
+                }
+            }
         }
     }
 
-    pub fn draw(&self, draw_command_buffer: vk::CommandBuffer, device: &ash::Device, pipeline_layout: vk::PipelineLayout) {
-        for x in 0..10 {
-            for y in 0..10 {
-                self.tiles[&[x, y]].draw(draw_command_buffer, device, pipeline_layout);
+    #[test]
+    fn the_whole_board_fits_in_clip_space() {
+        for size in [[5, 5], [10, 10], [7, 13]] {
+            let view_projection = board_camera(size, [800.0, 800.0]);
+            let board_px = [size[0] as f32 * Game::CELL_PX, size[1] as f32 * Game::CELL_PX];
+
+            let top_left = to_clip(&view_projection, [0.0, 0.0]);
+            let bottom_right = to_clip(&view_projection, board_px);
+            for clip in [top_left, bottom_right] {
+                assert!(clip.iter().all(|c| (-1.0001..=1.0001).contains(c)), "{size:?} board reaches {clip:?}");
             }
+            // the top left cell is at the top left of the screen, vulkan's clip space has y down too
+            assert!(top_left[0] < bottom_right[0] && top_left[1] < bottom_right[1]);
+        }
+    }
+
+    fn sprites_drawn(body: &[[i8; 2]], prev_body: &[[i8; 2]], tick_fraction: f32) -> Vec<(usize, [f32; 2])> {
+        let (body, prev_body) = (VecDeque::from(body.to_vec()), VecDeque::from(prev_body.to_vec()));
+        Game::sprite_points(&body, &prev_body, &Game::snake_points(&body, &prev_body, tick_fraction))
+    }
+
+    #[test]
+    fn moving_tail_keeps_the_last_cell() {
+        let (body, prev_body) = ([[5, 5], [4, 5], [3, 5]], [[4, 5], [3, 5], [2, 5]]);
+
+        for tick_fraction in [0.0, 0.5, 1.0] {
+            let drawn = sprites_drawn(&body, &prev_body, tick_fraction);
+            // head, middle, the cell the tail is sliding into and the sliding tail
+            assert_eq!(drawn.len(), 4, "at {tick_fraction}");
+            assert!(drawn.iter().any(|(_, point)| *point == [3.0, 5.0]));
+            assert!(drawn.iter().any(|(_, point)| *point == [2.0 + tick_fraction, 5.0]));
         }
     }
+
+    #[test]
+    fn grown_tail_is_drawn_once() {
+        let drawn = sprites_drawn(&[[5, 5], [4, 5], [3, 5], [2, 5]], &[[4, 5], [3, 5], [2, 5]], 0.5);
+
+        assert_eq!(drawn.len(), 4);
+        assert_eq!(drawn.iter().filter(|(_, point)| *point == [2.0, 5.0]).count(), 1);
+    }
+
+    #[test]
+    fn one_cell_snake_is_just_a_head() {
+        let drawn = sprites_drawn(&[[5, 5]], &[[4, 5]], 0.5);
+
+        assert_eq!(drawn, [(0, [4.5, 5.0])]);
+    }
 }
@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+use glfw::WindowEvent;
+use crate::engine::{draw::Layer, text::text_size, Engine};
+use super::theme::Theme;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PauseState {
+    /// the countdown is over, back to playing
+    Running,
+    Paused,
+    /// counting down back into the game, the snake starts moving at `until`
+    Resuming { until: Instant }
+}
+
+/// the pause screen and the countdown out of it, it only decides when the game should be stopped, the game loop does the stopping
+pub struct Pause {
+    state: PauseState
+}
+
+impl Pause {
+    /// how long the countdown after unpausing is
+    const COUNTDOWN: Duration = Duration::from_secs(3);
+    /// how dark the paused overlay makes the game
+    const DIM_ALPHA: f32 = 0.6;
+
+    /// starts paused
+    pub fn new() -> Pause {
+        log::debug!("Paused");
+        Pause {
+            state: PauseState::Paused
+        }
+    }
+
+    pub fn state(&self) -> PauseState {
+        self.state
+    }
+
+    /// true once the game should be ticking again
+    pub fn running(&self) -> bool {
+        self.state == PauseState::Running
+    }
+
+    pub fn pause(&mut self) {
+        if self.state != PauseState::Paused {
+            log::debug!("Paused");
+        }
+        self.state = PauseState::Paused;
+    }
+
+    /// pausing stops straight away, unpausing starts the countdown
+    pub fn toggle(&mut self) {
+        match self.state {
+            PauseState::Paused => self.state = PauseState::Resuming { until: Instant::now() + Pause::COUNTDOWN },
+            PauseState::Running | PauseState::Resuming { .. } => self.pause()
+        }
+    }
+
+    /// the window lost focus or got minimized this frame
+    pub fn lost_window(events: &[WindowEvent]) -> bool {
+        events.iter().any(|event| matches!(event, WindowEvent::Focus(false) | WindowEvent::Iconify(true)))
+    }
+
+    /// pauses again if the window is lost during the countdown and finishes the countdown, call once a frame
+    pub fn update(&mut self, events: &[WindowEvent]) {
+        if Pause::lost_window(events) {
+            self.pause();
+        }
+
+        if let PauseState::Resuming { until } = self.state {
+            if Instant::now() >= until {
+                log::debug!("Resumed");
+                self.state = PauseState::Running;
+            }
+        }
+    }
+
+    /// dims the screen with "paused" or the countdown in the middle
+    pub fn draw(&self, engine: &mut Engine, theme: &Theme) {
+        let text = match self.state {
+            PauseState::Running => return,
+            PauseState::Paused => "PAUSED".to_string(),
+            PauseState::Resuming { until } => {
+                let remaining = until.saturating_duration_since(Instant::now()).as_secs_f32();
+                format!("{}", remaining.ceil().max(1.0) as u32)
+            }
+        };
+
+        engine.fill_screen(Layer::Hud, theme.background.with_alpha(Pause::DIM_ALPHA).0);
+
+        let scale = 12.0;
+        let [screen_width, screen_height] = engine.viewport_size();
+        let (width, height) = text_size(&text);
+        let position = [
+            (screen_width - width as f32 * scale) / 2.0,
+            (screen_height - height as f32 * scale) / 2.0
+        ];
+        engine.draw_text(Layer::Hud, &text, position, scale, theme.hud_text.0);
+
+        if self.state == PauseState::Paused {
+            let hint = "P TO RESUME - Q FOR TITLE";
+            let hint_scale = 4.0;
+            let (hint_width, _) = text_size(hint);
+            engine.draw_text(
+                Layer::Hud,
+                hint,
+                [(screen_width - hint_width as f32 * hint_scale) / 2.0, position[1] + (height + 4) as f32 * scale],
+                hint_scale,
+                theme.hud_text.0
+            );
+        }
+    }
+}
+
+impl Default for Pause {
+    fn default() -> Self {
+        Pause::new()
+    }
+}
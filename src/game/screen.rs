@@ -0,0 +1,121 @@
+use crate::engine::{draw::Layer, text::text_size, Engine};
+use super::{high_scores::HighScores, pause::Pause, theme::Theme};
+
+/// which part of the game is up, the board is always drawn behind whichever it is
+pub enum Screen {
+    Title { selected: usize },
+    Options { selected: usize },
+    Playing,
+    Paused(Pause),
+    /// `rank` is the place on the high score list, if it made it
+    GameOver { score: u32, rank: Option<usize> },
+    HighScores
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TitleItem {
+    Play,
+    Options,
+    HighScores,
+    Quit
+}
+
+impl TitleItem {
+    pub const ALL: [TitleItem; 4] = [TitleItem::Play, TitleItem::Options, TitleItem::HighScores, TitleItem::Quit];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TitleItem::Play => "PLAY",
+            TitleItem::Options => "OPTIONS",
+            TitleItem::HighScores => "HIGH SCORES",
+            TitleItem::Quit => "QUIT"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionsItem {
+    Difficulty,
+    SpeedCurve,
+    Theme,
+    Back
+}
+
+impl OptionsItem {
+    pub const ALL: [OptionsItem; 4] = [OptionsItem::Difficulty, OptionsItem::SpeedCurve, OptionsItem::Theme, OptionsItem::Back];
+}
+
+impl Screen {
+    /// for logging transitions
+    pub fn name(&self) -> &'static str {
+        match self {
+            Screen::Title { .. } => "title",
+            Screen::Options { .. } => "options",
+            Screen::Playing => "playing",
+            Screen::Paused(_) => "paused",
+            Screen::GameOver { .. } => "game over",
+            Screen::HighScores => "high scores"
+        }
+    }
+}
+
+/// how dark menus make the board behind them
+const DIM_ALPHA: f32 = 0.75;
+const TITLE_SCALE: f32 = 10.0;
+const ITEM_SCALE: f32 = 5.0;
+const HINT_SCALE: f32 = 3.0;
+
+/// each line centred on its own, `y` is the top of the first line
+pub fn draw_centered_text(engine: &mut Engine, text: &str, y: f32, scale: f32, color: [f32; 4]) {
+    for (i, line) in text.lines().enumerate() {
+        let (width, height) = text_size(line);
+        let x = (engine.viewport_size()[0] - width as f32 * scale) / 2.0;
+        engine.draw_text(Layer::Hud, line, [x, y + (i as u32 * (height + 1)) as f32 * scale], scale, color);
+    }
+}
+
+/// dims the board with a big title, a list of items and a hint along the bottom,
+/// `selected` is drawn in the snake's colour on a highlighted bar
+pub fn draw_menu(engine: &mut Engine, theme: &Theme, title: &str, items: &[String], selected: Option<usize>, hint: &str) {
+    engine.fill_screen(Layer::Hud, theme.background.with_alpha(DIM_ALPHA).0);
+    let [screen_width, screen_height] = engine.viewport_size();
+
+    draw_centered_text(engine, title, 120.0, TITLE_SCALE, theme.hud_text.0);
+
+    let line_height = 8.0 * ITEM_SCALE;
+    let top = 300.0;
+    for (i, item) in items.iter().enumerate() {
+        let y = top + i as f32 * line_height;
+        let color = if Some(i) == selected {
+            engine.draw_rect(Layer::Hud, [0.0, y - ITEM_SCALE], [screen_width, 7.0 * ITEM_SCALE], theme.hud_background.0);
+            theme.snake_head.0
+        } else {
+            theme.hud_text.0
+        };
+        draw_centered_text(engine, item, y, ITEM_SCALE, color);
+    }
+
+    draw_centered_text(engine, hint, screen_height - 60.0, HINT_SCALE, theme.hud_text.0);
+}
+
+pub fn draw_game_over(engine: &mut Engine, theme: &Theme, score: u32, rank: Option<usize>) {
+    let mut items = vec![format!("SCORE {score}")];
+    if let Some(rank) = rank {
+        items.push(format!("NEW HIGH SCORE - {}", rank + 1));
+    }
+
+    draw_menu(engine, theme, "GAME OVER", &items, None, "ENTER TO PLAY AGAIN - ESC FOR TITLE");
+}
+
+pub fn draw_high_scores(engine: &mut Engine, theme: &Theme, high_scores: &HighScores) {
+    let items = if high_scores.scores().is_empty() {
+        vec!["NO SCORES YET".to_string()]
+    } else {
+        high_scores.scores().iter().enumerate().map(|(i, s)| {
+            let curve = if s.speed_curve { " CURVE" } else { "" };
+            format!("{:>2} {:>4} {}{curve}", i + 1, s.score, s.difficulty.name())
+        }).collect()
+    };
+
+    draw_menu(engine, theme, "HIGH SCORES", &items, None, "ESC TO GO BACK");
+}
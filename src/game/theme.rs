@@ -0,0 +1,137 @@
+use std::{fmt, fs, io, path::{Path, PathBuf}};
+use serde::Deserialize;
+
+/// every colour the game draws with, loaded from toml:
+///
+/// ```toml
+/// name = "classic"
+/// background = "#000000"
+/// snake_head = "#0000ff"
+/// hud_background = "#00000099"
+/// ...
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    /// clear colour behind everything
+    pub background: Color,
+    /// empty tiles
+    pub grid: Color,
+    /// the border around the board
+    pub wall: Color,
+    /// the body fades from `snake_head` to `snake_tail`
+    pub snake_head: Color,
+    pub snake_tail: Color,
+    pub apple: Color,
+    pub hud_text: Color,
+    pub hud_background: Color
+}
+
+/// linear rgba, written as `#rrggbb` or `#rrggbbaa` srgb hex in theme files
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Color(pub [f32; 4]);
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(PathBuf, io::Error),
+    Toml(toml::de::Error)
+}
+
+/// the built in themes, `--theme` takes any of these names or a path to a toml file
+pub const PRESETS: [(&str, &str); 3] = [
+    ("classic", include_str!("../../assets/themes/classic.toml")),
+    ("high-contrast", include_str!("../../assets/themes/high-contrast.toml")),
+    ("colorblind", include_str!("../../assets/themes/colorblind.toml"))
+];
+
+impl Theme {
+    pub fn classic() -> Theme {
+        Theme::preset(0)
+    }
+
+    /// panics if `index` is out of range or the embedded toml is broken
+    pub fn preset(index: usize) -> Theme {
+        toml::from_str(PRESETS[index].1).expect("Built in theme is invalid")
+    }
+
+    /// a preset name or a path to a theme file
+    pub fn load(name_or_path: &str) -> Result<Theme, ThemeError> {
+        if let Some(index) = PRESETS.iter().position(|(name, _)| name.eq_ignore_ascii_case(name_or_path)) {
+            return Ok(Theme::preset(index));
+        }
+
+        let path = Path::new(name_or_path);
+        let text = fs::read_to_string(path).map_err(|e| ThemeError::Io(path.to_path_buf(), e))?;
+        toml::from_str(&text).map_err(ThemeError::Toml)
+    }
+
+    /// body colour `t` of the way from head (0) to tail (1)
+    pub fn snake_color(&self, t: f32) -> Color {
+        let (head, tail) = (self.snake_head.0, self.snake_tail.0);
+        Color([0, 1, 2, 3].map(|i| head[i] + (tail[i] - head[i]) * t))
+    }
+}
+
+impl Color {
+    pub fn with_alpha(self, alpha: f32) -> Color {
+        let [r, g, b, a] = self.0;
+        Color([r, g, b, a * alpha])
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let hex = value.strip_prefix('#').unwrap_or(&value);
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+            return Err(format!("colour {value} should look like #rrggbb or #rrggbbaa"));
+        }
+
+        let mut channels = [1.0; 4];
+        for (i, channel) in channels.iter_mut().enumerate().take(hex.len() / 2) {
+            let byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|e| format!("colour {value}: {e}"))?;
+            *channel = byte as f32 / 255.0;
+        }
+
+        // the swapchain is srgb so shaders work in linear, alpha is already linear
+        for channel in channels.iter_mut().take(3) {
+            *channel = srgb_to_linear(*channel);
+        }
+
+        Ok(Color(channels))
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            ThemeError::Toml(e) => write!(f, "{e}")
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_are_named_what_you_type() {
+        for (index, (key, _)) in PRESETS.iter().enumerate() {
+            assert_eq!(Theme::preset(index).name, *key);
+            assert_eq!(Theme::load(key).unwrap().name, *key);
+        }
+    }
+}
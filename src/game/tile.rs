@@ -1,12 +1,13 @@
 use std::mem::size_of;
 use ash::vk;
-use crate::engine::{buffer::Buffer, descriptor::{DescriptorSet, UBO}, vertex::Vertex, px_to_screen, Engine};
+use crate::engine::{buffer::Buffer, descriptor::{DescriptorSet, UBO}, draw::{DrawCommand, Layer}, pipeline::PipelineSettings, texture::Sprite, vertex::Vertex, Engine};
 
-#[derive(Clone)]
 pub struct Tile {
     pub tile_state: TileState,
-    vertex_buffer: Buffer,
-    uniform_buffer: Buffer,
+    /// top left of the cell in pixels
+    position: [f32; 2],
+    vertex_buffer: Buffer<Vertex>,
+    uniform_buffer: Buffer<UBO>,
     descriptor_set: DescriptorSet
 }
 
@@ -14,37 +15,38 @@ impl Tile {
     pub fn new(
         position: [f32; 2],
         tile_state: TileState,
+        name: &str,
         engine: &Engine
     ) -> Tile {
+        // world pixels, the vertex shader puts them through the camera
+        let vertex = |x: f32, y: f32| Vertex::new([position[0] + x, position[1] + y]);
         let vertex_buffer = Buffer::new(
             &[
-                Vertex::new(px_to_screen(position[0] + 4.0, position[1] + 4.0)),
-                Vertex::new(px_to_screen(position[0] + 4.0, position[1] + 76.0)),
-                Vertex::new(px_to_screen(position[0] + 76.0, position[1] + 76.0)),
+                // bottom left, top left, top right then bottom left, top right, bottom right
+                vertex(4.0, 76.0),
+                vertex(4.0, 4.0),
+                vertex(76.0, 4.0),
 
-                Vertex::new(px_to_screen(position[0] + 4.0, position[1] + 4.0)),
-                Vertex::new(px_to_screen(position[0] + 76.0, position[1] + 76.0)),
-                Vertex::new(px_to_screen(position[0] + 76.0, position[1] + 4.0))
+                vertex(4.0, 76.0),
+                vertex(76.0, 4.0),
+                vertex(76.0, 76.0)
             ],
             vk::BufferUsageFlags::VERTEX_BUFFER,
             engine.device(),
-            engine.memory_properties(),
+            engine.allocator(),
             vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT
         );
 
         let uniform_buffer = Buffer::new(
             &[
+                // filled in from the theme every draw
                 UBO {
-                    color: match tile_state {
-                        TileState::Empty => [0.0, 0.0, 0.0],
-                        TileState::Snake => [0.0, 0.0, 1.0],
-                        TileState::Apple => [1.0, 0.0, 0.0]
-                    }
+                    color: [0.0; 4]
                 }
             ],
             vk::BufferUsageFlags::UNIFORM_BUFFER,
             engine.device(),
-            engine.memory_properties(),
+            engine.allocator(),
             vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT
         );
 
@@ -58,51 +60,52 @@ impl Tile {
                     .build()
             )
             .uniform(uniform_buffer.handle(), size_of::<UBO>() as u64)
+            .name(&format!("{name} descriptor set"))
             .build(engine);
 
+        engine.name_object(vertex_buffer.handle(), &format!("{name} vertex"));
+        engine.name_object(uniform_buffer.handle(), &format!("{name} uniform"));
+
         Tile {
             tile_state,
+            position,
             vertex_buffer,
             uniform_buffer,
             descriptor_set
         }
     }
 
-    pub fn draw(&self, draw_command_buffer: vk::CommandBuffer, device: &ash::Device, pipeline_layout: vk::PipelineLayout) {
-        unsafe {
-            self.uniform_buffer.set_buffer(&[
-                UBO {
-                    color: match self.tile_state {
-                        TileState::Empty => [0.0, 0.0, 0.0],
-                        TileState::Snake => [0.0, 0.0, 1.0],
-                        TileState::Apple => [1.0, 0.0, 0.0]
-                    }
-                }
-            ]);
-            self.descriptor_set.write_descriptor_set(device);
+    /// straight rgba, alpha fades the tile into whatever was drawn under it
+    pub fn draw(&self, engine: &mut Engine, layer: Layer, color: [f32; 4]) {
+        self.draw_with(engine, layer, color, None);
+    }
 
-            device.cmd_bind_descriptor_sets(
-                draw_command_buffer,
-                vk::PipelineBindPoint::GRAPHICS,
-                pipeline_layout,
-                0,
-                &[self.descriptor_set.descriptor_set()],
-                &[]
-            );
-            device.cmd_bind_vertex_buffers(
-                draw_command_buffer,
-                0,
-                &[self.vertex_buffer.handle()],
-                &[0]
-            );
-            device.cmd_draw(
-                draw_command_buffer,
-                self.vertex_buffer.count(),
-                1,
-                0,
-                0
+    /// draws `sprite` over the tile instead of the flat colour if there is one, only `color`'s alpha is used then
+    pub fn draw_with(&self, engine: &mut Engine, layer: Layer, color: [f32; 4], sprite: Option<Sprite>) {
+        if let Some(sprite) = sprite {
+            engine.draw_sprite(
+                layer,
+                sprite,
+                [self.position[0] + 4.0, self.position[1] + 4.0],
+                [72.0, 72.0],
+                [1.0, 1.0, 1.0, color[3]]
             );
+            return;
         }
+
+        self.uniform_buffer.set_buffer(&[
+            UBO {
+                color
+            }
+        ]).unwrap();
+        self.descriptor_set.write_descriptor_set(&engine.device());
+
+        engine.submit(layer, DrawCommand::Mesh {
+            pipeline: PipelineSettings::PREMULTIPLIED,
+            vertex_buffer: self.vertex_buffer.handle(),
+            vertex_count: self.vertex_buffer.count(),
+            descriptor_set: Some(self.descriptor_set.descriptor_set())
+        });
     }
 }
 
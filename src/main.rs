@@ -1,52 +1,321 @@
 pub mod engine;
 pub mod game;
 
-use std::time::Instant;
-use engine::Engine;
-use game::{tile::Tile, Game, Direction};
-use glfw::{Key, Action};
+use std::{env, path::PathBuf, time::Instant};
+use engine::{config::{arg_value, env_flag, has_flag, EngineConfig}, game_loop::GameLoop, paths::data_dir, sprite_sheet::SpriteSheet, Engine};
+use game::{controls::{Control, Controls}, difficulty::{Difficulty, Speed}, high_scores::HighScores, pause::{Pause, PauseState}, screen::{draw_game_over, draw_high_scores, draw_menu, OptionsItem, Screen, TitleItem}, theme::{Theme, PRESETS}, tile::Tile, Game, TickOutcome, TileSprites};
 
 fn main() {
-    let mut engine = Engine::new();
-
-    let mut game = Game::new(&engine);
-
-    let mut accept_input = true;
-    let mut prev_time = Instant::now();
-    while engine.running() {
-        if accept_input {   
-            let window = engine.window();
-            if window.get_key(Key::W) == Action::Press {
-                game.input(Direction::North);
-                accept_input = false;
-            } else if window.get_key(Key::S) == Action::Press {
-                game.input(Direction::South);
-                accept_input = false;
-            } else if window.get_key(Key::A) == Action::Press {
-                game.input(Direction::West);
-                accept_input = false;
-            } else if window.get_key(Key::D) == Action::Press {
-                game.input(Direction::East);
-                accept_input = false;
-            }
-        }
-        
-        let crnt_time = Instant::now();
-        if (crnt_time - prev_time).as_secs_f32() >= 1.0 / 8.0 {
-            game.tick();
-            prev_time = crnt_time;
-            accept_input = true;
-        }
-
-        engine.begin_draw();
-        let draw_command_buffer = engine.draw_command_buffer();
-        let device = engine.device();
-        let pipeline_layout = engine.pipeline_layout();
-        game.draw(draw_command_buffer, &device, pipeline_layout);
-        engine.end_draw();
+    let config = EngineConfig::from_env();
+    // before the window opens so it doesn't flash up
+    if config.list_gpus {
+        Engine::list_gpus();
+        return;
     }
+    let mut engine = Engine::new(config);
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let difficulty = match arg_value(&args, "--difficulty").or_else(|| env::var("SNAKE_DIFFICULTY").ok()) {
+        Some(name) => Difficulty::parse(&name).unwrap_or_else(|| {
+            log::error!("Unknown difficulty {name}, expected easy, normal, hard or insane");
+            Difficulty::default()
+        }),
+        None => Difficulty::default()
+    };
+    let speed_curve = has_flag(&args, "--speed-curve") || env_flag("SNAKE_SPEED_CURVE");
+
+    // sits frozen behind the title screen until the first game starts
+    let mut game = Game::new(&engine, Speed::new(difficulty, speed_curve));
+    // the board happens to be the size of the window but this keeps it on screen if either changes
+    let viewport = engine.viewport_size();
+    engine.camera_mut().fit([0.0, 0.0], Game::board_px(), viewport);
+
+    let sprite_sheet = arg_value(&args, "--sprites")
+        .or_else(|| env::var("SNAKE_SPRITES").ok())
+        .and_then(|path| match SpriteSheet::load(&engine, &PathBuf::from(&path)) {
+            Ok(sheet) => Some(sheet),
+            Err(e) => {
+                log::error!("Failed to load sprite sheet {path}: {e}");
+                None
+            }
+        });
+    let start_time = Instant::now();
+
+    let theme_arg = arg_value(&args, "--theme").or_else(|| env::var("SNAKE_THEME").ok());
+    let theme = match &theme_arg {
+        Some(theme) => Theme::load(theme).unwrap_or_else(|e| {
+            log::error!("Failed to load theme {theme}: {e}");
+            Theme::classic()
+        }),
+        None => Theme::classic()
+    };
+    game.set_theme(theme, &mut engine);
+    // cycle_theme (T) goes through the presets, a custom theme goes back to the first one
+    let theme_index = match &theme_arg {
+        Some(theme) => PRESETS.iter().position(|(name, _)| name.eq_ignore_ascii_case(theme)).unwrap_or(PRESETS.len() - 1),
+        None => 0
+    };
+
+    // an explicit path has to load, the one in the data dir is only used if it's there
+    let controls = match arg_value(&args, "--controls").or_else(|| env::var("SNAKE_CONTROLS").ok()) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => data_dir().map(|dir| dir.join("controls.toml")).filter(|path| path.exists())
+    };
+    let controls = match controls {
+        Some(path) => Controls::load(&path).unwrap_or_else(|e| {
+            log::error!("Failed to load controls {}: {e}", path.display());
+            Controls::new()
+        }),
+        None => Controls::new()
+    };
+
+    let mut app = App {
+        game,
+        controls,
+        settings: Speed::new(difficulty, speed_curve),
+        screen: Screen::Title { selected: 0 },
+        high_scores: HighScores::load(),
+        accept_input: true,
+        theme_index,
+        sprite_sheet,
+        start_time
+    };
+    let mut game_loop = GameLoop::new(app.game.speed().tick_rate());
+    game_loop.set_paused(true);
+    game_loop.run(&mut engine, &mut app, App::update, App::render);
 
     unsafe {
         engine.device().device_wait_idle().unwrap();
     }
+
+    log::debug!("Gpu memory at exit:\n{}", engine.allocator().borrow().stats());
+}
+
+/// everything the loop callbacks need between frames
+struct App {
+    game: Game,
+    controls: Controls,
+    /// difficulty and speed curve for the next game, changed from the options screen
+    settings: Speed,
+    screen: Screen,
+    high_scores: HighScores,
+    /// one turn per tick so two quick presses can't turn the snake into itself
+    accept_input: bool,
+    theme_index: usize,
+    sprite_sheet: Option<SpriteSheet>,
+    start_time: Instant
+}
+
+impl App {
+    /// only runs while playing, the game loop is paused on every other screen
+    fn update(&mut self, _engine: &mut Engine, game_loop: &mut GameLoop) {
+        match self.game.tick() {
+            TickOutcome::Died => {
+                // stop any catch up steps left this frame
+                game_loop.set_paused(true);
+
+                let score = self.game.score();
+                let rank = self.high_scores.insert(score, self.game.speed());
+                if rank.is_some() {
+                    self.high_scores.save();
+                }
+                log::info!(score = score; "Game over");
+                self.set_screen(Screen::GameOver { score, rank });
+            }
+            TickOutcome::Moved | TickOutcome::AteApple => {
+                self.accept_input = true;
+                // the speed curve can change it every apple
+                game_loop.set_tick_rate(self.game.speed().tick_rate());
+            }
+        }
+    }
+
+    /// input is read every frame so short taps between ticks still count
+    fn render(&mut self, engine: &mut Engine, game_loop: &mut GameLoop) {
+        self.controls.update(engine);
+
+        if self.controls.pressed(engine, Control::CycleTheme) {
+            self.next_theme(engine);
+        }
+
+        match &mut self.screen {
+            Screen::Title { selected } => {
+                *selected = App::menu_selection(&self.controls, engine, *selected, TitleItem::ALL.len());
+                if self.controls.pressed(engine, Control::Select) {
+                    match TitleItem::ALL[*selected] {
+                        TitleItem::Play => self.new_game(engine, game_loop),
+                        TitleItem::Options => self.set_screen(Screen::Options { selected: 0 }),
+                        TitleItem::HighScores => self.set_screen(Screen::HighScores),
+                        TitleItem::Quit => engine.close()
+                    }
+                } else if self.pressed(engine, Control::Quit) {
+                    engine.close();
+                }
+            }
+            Screen::Options { selected } => {
+                *selected = App::menu_selection(&self.controls, engine, *selected, OptionsItem::ALL.len());
+                let item = OptionsItem::ALL[*selected];
+                let step = if self.pressed(engine, Control::TurnWest) {
+                    -1
+                } else if self.pressed(engine, Control::TurnEast) || self.pressed(engine, Control::Select) {
+                    1
+                } else {
+                    0
+                };
+
+                if self.menu_back(engine) || (item == OptionsItem::Back && step != 0) {
+                    self.set_screen(Screen::Title { selected: 1 });
+                } else if step != 0 {
+                    self.change_option(engine, item, step);
+                }
+            }
+            Screen::Playing => {
+                if self.pressed(engine, Control::Pause) || Pause::lost_window(engine.window_events()) {
+                    self.set_screen(Screen::Paused(Pause::new()));
+                } else if self.pressed(engine, Control::Restart) {
+                    self.new_game(engine, game_loop);
+                } else if self.pressed(engine, Control::Quit) {
+                    self.set_screen(Screen::Title { selected: 0 });
+                } else {
+                    self.steer(engine);
+                }
+            }
+            Screen::Paused(pause) => {
+                if self.controls.pressed(engine, Control::Quit) && pause.state() == PauseState::Paused {
+                    self.set_screen(Screen::Title { selected: 0 });
+                } else if self.controls.pressed(engine, Control::Restart) {
+                    self.new_game(engine, game_loop);
+                } else {
+                    if self.controls.pressed(engine, Control::Pause) {
+                        pause.toggle();
+                    }
+                    pause.update(engine.window_events());
+                    if pause.running() {
+                        self.set_screen(Screen::Playing);
+                    }
+                }
+            }
+            Screen::GameOver { .. } => {
+                if self.pressed(engine, Control::Select) || self.pressed(engine, Control::Restart) {
+                    self.new_game(engine, game_loop);
+                } else if self.menu_back(engine) {
+                    self.set_screen(Screen::Title { selected: 0 });
+                }
+            }
+            Screen::HighScores => {
+                if self.menu_back(engine) || self.pressed(engine, Control::Select) {
+                    self.set_screen(Screen::Title { selected: 2 });
+                }
+            }
+        }
+        game_loop.set_paused(!matches!(self.screen, Screen::Playing));
+
+        if let Some(sprite_sheet) = &self.sprite_sheet {
+            self.game.set_sprites(TileSprites::from_sheet(sprite_sheet, self.start_time.elapsed().as_secs_f32()));
+        }
+
+        self.game.draw(engine, game_loop.alpha());
+        self.draw_screen(engine);
+    }
+
+    fn set_screen(&mut self, screen: Screen) {
+        log::debug!(from = self.screen.name(), to = screen.name(); "Changed screen");
+        self.screen = screen;
+    }
+
+    /// throws the old board away and starts playing with the current settings.
+    /// only called from `render`, `begin_draw` has already waited for the last frame so the old tiles are safe to drop
+    fn new_game(&mut self, engine: &mut Engine, game_loop: &mut GameLoop) {
+        let theme = self.game.theme().clone();
+        self.game = Game::new(engine, self.settings);
+        self.game.set_theme(theme, engine);
+        self.accept_input = true;
+        game_loop.set_tick_rate(self.game.speed().tick_rate());
+        // time spent in the menus shouldn't count towards the first tick
+        game_loop.reset();
+        self.set_screen(Screen::Playing);
+    }
+
+    fn steer(&mut self, engine: &Engine) {
+        if !self.accept_input {
+            return;
+        }
+
+        if let Some(direction) = self.controls.held_direction(engine) {
+            self.game.input(direction);
+            self.accept_input = false;
+        }
+    }
+
+    fn next_theme(&mut self, engine: &mut Engine) {
+        self.theme_index = (self.theme_index + 1) % PRESETS.len();
+        self.set_theme(engine);
+    }
+
+    fn set_theme(&mut self, engine: &mut Engine) {
+        let theme = Theme::preset(self.theme_index);
+        log::info!(theme = theme.name.as_str(); "Switched theme");
+        self.game.set_theme(theme, engine);
+    }
+
+    /// moves a menu selection up or down, wrapping around the ends
+    fn menu_selection(controls: &Controls, engine: &Engine, selected: usize, count: usize) -> usize {
+        if controls.pressed(engine, Control::TurnNorth) {
+            (selected + count - 1) % count
+        } else if controls.pressed(engine, Control::TurnSouth) {
+            (selected + 1) % count
+        } else {
+            selected
+        }
+    }
+
+    fn pressed(&self, engine: &Engine, control: Control) -> bool {
+        self.controls.pressed(engine, control)
+    }
+
+    /// quit or pause both leave a menu
+    fn menu_back(&self, engine: &Engine) -> bool {
+        self.pressed(engine, Control::Quit) || self.pressed(engine, Control::Pause)
+    }
+
+    /// `step` is -1 or 1, for the left and right keys
+    fn change_option(&mut self, engine: &mut Engine, item: OptionsItem, step: isize) {
+        let cycle = |index: usize, count: usize| (index as isize + step).rem_euclid(count as isize) as usize;
+
+        match item {
+            OptionsItem::Difficulty => {
+                let index = Difficulty::ALL.iter().position(|d| *d == self.settings.difficulty).unwrap();
+                self.settings.difficulty = Difficulty::ALL[cycle(index, Difficulty::ALL.len())];
+            }
+            OptionsItem::SpeedCurve => self.settings.speed_curve = !self.settings.speed_curve,
+            OptionsItem::Theme => {
+                self.theme_index = cycle(self.theme_index, PRESETS.len());
+                self.set_theme(engine);
+            }
+            OptionsItem::Back => {}
+        }
+    }
+
+    fn draw_screen(&self, engine: &mut Engine) {
+        let theme = self.game.theme();
+        match &self.screen {
+            Screen::Title { selected } => {
+                let items = TitleItem::ALL.map(|item| item.label().to_string());
+                draw_menu(engine, theme, "SNAKE", &items, Some(*selected), "W/S TO MOVE - ENTER TO PICK");
+            }
+            Screen::Options { selected } => {
+                let items = OptionsItem::ALL.map(|item| match item {
+                    OptionsItem::Difficulty => format!("DIFFICULTY {}", self.settings.difficulty.name()),
+                    OptionsItem::SpeedCurve => format!("SPEED CURVE {}", if self.settings.speed_curve { "ON" } else { "OFF" }),
+                    OptionsItem::Theme => format!("THEME {}", theme.name),
+                    OptionsItem::Back => "BACK".to_string()
+                });
+                draw_menu(engine, theme, "OPTIONS", &items, Some(*selected), "A/D TO CHANGE - ESC TO GO BACK");
+            }
+            Screen::Playing => {}
+            Screen::Paused(pause) => pause.draw(engine, theme),
+            Screen::GameOver { score, rank } => draw_game_over(engine, theme, *score, *rank),
+            Screen::HighScores => draw_high_scores(engine, theme, &self.high_scores)
+        }
+    }
 }